@@ -1,73 +0,0 @@
-import { Buffer } from "buffer";
-import { AssembledTransaction, Client as ContractClient, ClientOptions as ContractClientOptions, MethodOptions, Result } from "@stellar/stellar-sdk/contract";
-import type { u64, i128 } from "@stellar/stellar-sdk/contract";
-export * from "@stellar/stellar-sdk";
-export * as contract from "@stellar/stellar-sdk/contract";
-export * as rpc from "@stellar/stellar-sdk/rpc";
-export declare const Errors: {
-    1: {
-        message: string;
-    };
-    2: {
-        message: string;
-    };
-    3: {
-        message: string;
-    };
-};
-export interface CommitmentSpec {
-    amount: i128;
-    metadata_hash: Buffer;
-    provider: string;
-    unlock_date: u64;
-}
-export interface Client {
-    /**
-     * Construct and simulate a initialize transaction. Returns an `AssembledTransaction` object which will have a `result` field containing the result of the simulation. If this transaction changes contract state, you will need to call `signAndSend()` on the returned object.
-     * Initialize the commitment system
-     */
-    initialize: ({ _admin }: {
-        _admin: string;
-    }, options?: MethodOptions) => Promise<AssembledTransaction<Result<void>>>;
-    /**
-     * Construct and simulate a get_commitment transaction. Returns an `AssembledTransaction` object which will have a `result` field containing the result of the simulation. If this transaction changes contract state, you will need to call `signAndSend()` on the returned object.
-     * Fetch an existing commitment
-     */
-    get_commitment: ({ _id }: {
-        _id: u64;
-    }, options?: MethodOptions) => Promise<AssembledTransaction<Result<CommitmentSpec>>>;
-    /**
-     * Construct and simulate a create_commitment transaction. Returns an `AssembledTransaction` object which will have a `result` field containing the result of the simulation. If this transaction changes contract state, you will need to call `signAndSend()` on the returned object.
-     * Create a new commitment
-     */
-    create_commitment: ({ _spec }: {
-        _spec: CommitmentSpec;
-    }, options?: MethodOptions) => Promise<AssembledTransaction<Result<u64>>>;
-    /**
-     * Construct and simulate a revoke_commitment transaction. Returns an `AssembledTransaction` object which will have a `result` field containing the result of the simulation. If this transaction changes contract state, you will need to call `signAndSend()` on the returned object.
-     * Revoke a commitment
-     */
-    revoke_commitment: ({ _id }: {
-        _id: u64;
-    }, options?: MethodOptions) => Promise<AssembledTransaction<Result<void>>>;
-}
-export declare class Client extends ContractClient {
-    readonly options: ContractClientOptions;
-    static deploy<T = Client>(
-    /** Options for initializing a Client as well as for calling a method, with extras specific to deploying. */
-    options: MethodOptions & Omit<ContractClientOptions, "contractId"> & {
-        /** The hash of the Wasm blob, which must already be installed on-chain. */
-        wasmHash: Buffer | string;
-        /** Salt used to generate the contract's ID. Passed through to {@link Operation.createCustomContract}. Default: random. */
-        salt?: Buffer | Uint8Array;
-        /** The format used to decode `wasmHash`, if it's provided as a string. */
-        format?: "hex" | "base64";
-    }): Promise<AssembledTransaction<T>>;
-    constructor(options: ContractClientOptions);
-    readonly fromJSON: {
-        initialize: (json: string) => AssembledTransaction<Result<void, import("@stellar/stellar-sdk/contract").ErrorMessage>>;
-        get_commitment: (json: string) => AssembledTransaction<Result<CommitmentSpec, import("@stellar/stellar-sdk/contract").ErrorMessage>>;
-        create_commitment: (json: string) => AssembledTransaction<Result<bigint, import("@stellar/stellar-sdk/contract").ErrorMessage>>;
-        revoke_commitment: (json: string) => AssembledTransaction<Result<void, import("@stellar/stellar-sdk/contract").ErrorMessage>>;
-    };
-}
@@ -1,108 +0,0 @@
-import { Buffer } from "buffer";
-import { Address } from "@stellar/stellar-sdk";
-import {
-  AssembledTransaction,
-  Client as ContractClient,
-  ClientOptions as ContractClientOptions,
-  MethodOptions,
-  Result,
-  Spec as ContractSpec,
-} from "@stellar/stellar-sdk/contract";
-import type {
-  u32,
-  i32,
-  u64,
-  i64,
-  u128,
-  i128,
-  u256,
-  i256,
-  Option,
-  Timepoint,
-  Duration,
-} from "@stellar/stellar-sdk/contract";
-export * from "@stellar/stellar-sdk";
-export * as contract from "@stellar/stellar-sdk/contract";
-export * as rpc from "@stellar/stellar-sdk/rpc";
-
-if (typeof window !== "undefined") {
-  //@ts-ignore Buffer exists
-  window.Buffer = window.Buffer || Buffer;
-}
-
-
-
-
-export const Errors = {
-  1: {message:"NotFound"},
-  2: {message:"Unauthorized"},
-  3: {message:"AlreadyInitialized"}
-}
-
-
-export interface CommitmentSpec {
-  amount: i128;
-  metadata_hash: Buffer;
-  provider: string;
-  unlock_date: u64;
-}
-
-export interface Client {
-  /**
-   * Construct and simulate a initialize transaction. Returns an `AssembledTransaction` object which will have a `result` field containing the result of the simulation. If this transaction changes contract state, you will need to call `signAndSend()` on the returned object.
-   * Initialize the commitment system
-   */
-  initialize: ({_admin}: {_admin: string}, options?: MethodOptions) => Promise<AssembledTransaction<Result<void>>>
-
-  /**
-   * Construct and simulate a get_commitment transaction. Returns an `AssembledTransaction` object which will have a `result` field containing the result of the simulation. If this transaction changes contract state, you will need to call `signAndSend()` on the returned object.
-   * Fetch an existing commitment
-   */
-  get_commitment: ({_id}: {_id: u64}, options?: MethodOptions) => Promise<AssembledTransaction<Result<CommitmentSpec>>>
-
-  /**
-   * Construct and simulate a create_commitment transaction. Returns an `AssembledTransaction` object which will have a `result` field containing the result of the simulation. If this transaction changes contract state, you will need to call `signAndSend()` on the returned object.
-   * Create a new commitment
-   */
-  create_commitment: ({_spec}: {_spec: CommitmentSpec}, options?: MethodOptions) => Promise<AssembledTransaction<Result<u64>>>
-
-  /**
-   * Construct and simulate a revoke_commitment transaction. Returns an `AssembledTransaction` object which will have a `result` field containing the result of the simulation. If this transaction changes contract state, you will need to call `signAndSend()` on the returned object.
-   * Revoke a commitment
-   */
-  revoke_commitment: ({_id}: {_id: u64}, options?: MethodOptions) => Promise<AssembledTransaction<Result<void>>>
-
-}
-export class Client extends ContractClient {
-  static async deploy<T = Client>(
-    /** Options for initializing a Client as well as for calling a method, with extras specific to deploying. */
-    options: MethodOptions &
-      Omit<ContractClientOptions, "contractId"> & {
-        /** The hash of the Wasm blob, which must already be installed on-chain. */
-        wasmHash: Buffer | string;
-        /** Salt used to generate the contract's ID. Passed through to {@link Operation.createCustomContract}. Default: random. */
-        salt?: Buffer | Uint8Array;
-        /** The format used to decode `wasmHash`, if it's provided as a string. */
-        format?: "hex" | "base64";
-      }
-  ): Promise<AssembledTransaction<T>> {
-    return ContractClient.deploy(null, options)
-  }
-  constructor(public readonly options: ContractClientOptions) {
-    super(
-      new ContractSpec([ "AAAAAAAAACBJbml0aWFsaXplIHRoZSBjb21taXRtZW50IHN5c3RlbQAAAAppbml0aWFsaXplAAAAAAABAAAAAAAAAAZfYWRtaW4AAAAAABMAAAABAAAD6QAAA+0AAAAAAAAAAw==",
-        "AAAAAAAAABxGZXRjaCBhbiBleGlzdGluZyBjb21taXRtZW50AAAADmdldF9jb21taXRtZW50AAAAAAABAAAAAAAAAANfaWQAAAAABgAAAAEAAAPpAAAH0AAAAA5Db21taXRtZW50U3BlYwAAAAAAAw==",
-        "AAAAAAAAABdDcmVhdGUgYSBuZXcgY29tbWl0bWVudAAAAAARY3JlYXRlX2NvbW1pdG1lbnQAAAAAAAABAAAAAAAAAAVfc3BlYwAAAAAAB9AAAAAOQ29tbWl0bWVudFNwZWMAAAAAAAEAAAPpAAAABgAAAAM=",
-        "AAAAAAAAABNSZXZva2UgYSBjb21taXRtZW50AAAAABFyZXZva2VfY29tbWl0bWVudAAAAAAAAAEAAAAAAAAAA19pZAAAAAAGAAAAAQAAA+kAAAPtAAAAAAAAAAM=",
-        "AAAABAAAAAAAAAAAAAAABUVycm9yAAAAAAAAAwAAAAAAAAAITm90Rm91bmQAAAABAAAAAAAAAAxVbmF1dGhvcml6ZWQAAAACAAAAAAAAABJBbHJlYWR5SW5pdGlhbGl6ZWQAAAAAAAM=",
-        "AAAAAQAAAAAAAAAAAAAADkNvbW1pdG1lbnRTcGVjAAAAAAAEAAAAAAAAAAZhbW91bnQAAAAAAAsAAAAAAAAADW1ldGFkYXRhX2hhc2gAAAAAAAPuAAAAIAAAAAAAAAAIcHJvdmlkZXIAAAATAAAAAAAAAAt1bmxvY2tfZGF0ZQAAAAAG" ]),
-      options
-    )
-  }
-  public readonly fromJSON = {
-    initialize: this.txFromJSON<Result<void>>,
-        get_commitment: this.txFromJSON<Result<CommitmentSpec>>,
-        create_commitment: this.txFromJSON<Result<u64>>,
-        revoke_commitment: this.txFromJSON<Result<void>>
-  }
-}
\ No newline at end of file
@@ -123,7 +123,7 @@ fn benchmark_allocate() {
         let _ = AllocationStrategiesContract::allocate(
             e.clone(),
             caller.clone(),
-            1,
+            commitment_interface::types::commitment_id(&e, 1),
             1000_0000000,
             Strategy::Safe,
         );
@@ -154,7 +154,7 @@ fn benchmark_get_allocation() {
         AllocationStrategiesContract::allocate(
             e.clone(),
             caller.clone(),
-            1,
+            commitment_interface::types::commitment_id(&e, 1),
             1000_0000000,
             Strategy::Safe,
         )
@@ -165,7 +165,10 @@ fn benchmark_get_allocation() {
 
     e.as_contract(&contract_id, || {
         let start = e.ledger().sequence();
-        AllocationStrategiesContract::get_allocation(e.clone(), 1);
+        AllocationStrategiesContract::get_allocation(
+            e.clone(),
+            commitment_interface::types::commitment_id(&e, 1),
+        );
         let end = e.ledger().sequence();
         metrics.record_gas(start, end);
     });
@@ -232,7 +235,7 @@ fn benchmark_batch_allocate() {
             let _ = AllocationStrategiesContract::allocate(
                 e.clone(),
                 caller.clone(),
-                i,
+                commitment_interface::types::commitment_id(&e, i),
                 1000_0000000,
                 Strategy::Safe,
             );
@@ -1,13 +1,15 @@
 // Allocation Strategies Contract
 #![no_std]
 
+use commitment_interface::types::{commitment_id as commitment_id_for, Commitment, OperatorScope};
+use commitment_interface::CommitmentClient;
 use shared_utils::{Pausable, RateLimiter};
 use soroban_sdk::{
     contract, contracterror, contractimpl, contracttype, symbol_short, Address, BytesN, Env, Map,
-    Symbol, Vec,
+    String, Symbol, Vec,
 };
 
-const CURRENT_VERSION: u32 = 1;
+const CURRENT_VERSION: u32 = 2;
 
 // ============================================================================
 // ERROR CODES - Error Handling
@@ -32,6 +34,8 @@ pub enum Error {
     InvalidWasmHash = 15,
     InvalidVersion = 16,
     AlreadyMigrated = 17,
+    CommitmentNotFound = 18,
+    CommitmentNotActive = 19,
 }
 
 // ============================================================================
@@ -70,7 +74,7 @@ pub struct Pool {
 #[contracttype]
 #[derive(Clone, Debug)]
 pub struct Allocation {
-    pub commitment_id: u64,
+    pub commitment_id: String,
     pub pool_id: u32,
     pub amount: i128,
     pub timestamp: u64,
@@ -79,7 +83,7 @@ pub struct Allocation {
 #[contracttype]
 #[derive(Clone, Debug)]
 pub struct AllocationSummary {
-    pub commitment_id: u64,
+    pub commitment_id: String,
     pub strategy: Strategy,
     pub total_allocated: i128,
     pub allocations: Vec<Allocation>,
@@ -93,16 +97,35 @@ pub struct AllocationSummary {
 #[derive(Clone)]
 pub enum DataKey {
     Pool(u32),
-    Allocations(u64),
-    Strategy(u64),
+    Allocations(String),
+    Strategy(String),
     CommitmentCore,
     Admin,
     Initialized,
     ReentrancyGuard,
-    PoolRegistry,         // Vec<u32> of all pool IDs
-    TotalAllocated(u64),  // Total amount allocated per commitment
-    AllocationOwner(u64), // Track allocation ownership
-    Version,              // Contract version
+    PoolRegistry,           // Vec<u32> of all pool IDs
+    TotalAllocated(String), // Total amount allocated per commitment
+    Version,                // Contract version
+}
+
+/// Version 1 keys, which used numeric commitment ids. Only read by `migrate_allocations`.
+#[contracttype]
+#[derive(Clone)]
+pub enum LegacyDataKey {
+    Allocations(u64),
+    Strategy(u64),
+    TotalAllocated(u64),
+    AllocationOwner(u64),
+}
+
+/// Version 1 `Allocation`, as stored under `LegacyDataKey::Allocations`.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct LegacyAllocation {
+    pub commitment_id: u64,
+    pub pool_id: u32,
+    pub amount: i128,
+    pub timestamp: u64,
 }
 
 // ============================================================================
@@ -280,6 +303,9 @@ impl AllocationStrategiesContract {
     /// - Contract is initialized
     /// - `amount > 0`
     /// - `reentrancy_guard == false`
+    /// - `commitment_id` exists in commitment_core, is open and is owned by `caller` or
+    ///   `caller` is an operator approved in core for `OperatorScope::Allocate`
+    /// - `amount <= current_value` of the commitment in core
     /// - No existing allocation for `commitment_id`
     ///
     /// **Postconditions:**
@@ -297,7 +323,7 @@ impl AllocationStrategiesContract {
     pub fn allocate(
         env: Env,
         caller: Address,
-        commitment_id: u64,
+        commitment_id: String,
         amount: i128,
         strategy: Strategy,
     ) -> Result<AllocationSummary, Error> {
@@ -321,25 +347,36 @@ impl AllocationStrategiesContract {
             return Err(Error::InvalidAmount);
         }
 
+        // The commitment must exist in commitment_core and belong to the caller (or the
+        // caller must operate it for the owner)
+        let commitment = match Self::require_commitment_owner(&env, &caller, &commitment_id) {
+            Ok(commitment) => commitment,
+            Err(err) => {
+                Self::set_reentrancy_guard(&env, false);
+                return Err(err);
+            }
+        };
+
         // Check for existing allocation (prevent double allocation)
         if env
             .storage()
             .persistent()
-            .has(&DataKey::Allocations(commitment_id))
+            .has(&DataKey::Allocations(commitment_id.clone()))
         {
             Self::set_reentrancy_guard(&env, false);
             return Err(Error::AlreadyInitialized);
         }
 
-        // Store allocation ownership
-        env.storage()
-            .persistent()
-            .set(&DataKey::AllocationOwner(commitment_id), &caller);
+        // A commitment holds at most one allocation, so it can cover up to its whole value
+        if amount > commitment.current_value {
+            Self::set_reentrancy_guard(&env, false);
+            return Err(Error::InvalidAmount);
+        }
 
         // Store the strategy
         env.storage()
             .persistent()
-            .set(&DataKey::Strategy(commitment_id), &strategy);
+            .set(&DataKey::Strategy(commitment_id.clone()), &strategy);
 
         // Get pools based on strategy
         let pools = Self::select_pools(&env, strategy)?;
@@ -389,7 +426,7 @@ impl AllocationStrategiesContract {
 
             // Record allocation
             let allocation = Allocation {
-                commitment_id,
+                commitment_id: commitment_id.clone(),
                 pool_id,
                 amount: alloc_amount,
                 timestamp: env.ledger().timestamp(),
@@ -412,17 +449,18 @@ impl AllocationStrategiesContract {
         // Store allocations
         env.storage()
             .persistent()
-            .set(&DataKey::Allocations(commitment_id), &allocations);
-        env.storage()
-            .persistent()
-            .set(&DataKey::TotalAllocated(commitment_id), &total_allocated);
+            .set(&DataKey::Allocations(commitment_id.clone()), &allocations);
+        env.storage().persistent().set(
+            &DataKey::TotalAllocated(commitment_id.clone()),
+            &total_allocated,
+        );
 
         // Clear reentrancy guard
         Self::set_reentrancy_guard(&env, false);
 
         // Emit event
         env.events().publish(
-            (symbol_short!("allocate"), commitment_id),
+            (symbol_short!("allocate"), commitment_id.clone()),
             (strategy, amount),
         );
 
@@ -437,7 +475,7 @@ impl AllocationStrategiesContract {
     pub fn rebalance(
         env: Env,
         caller: Address,
-        commitment_id: u64,
+        commitment_id: String,
     ) -> Result<AllocationSummary, Error> {
         caller.require_auth();
         Self::require_initialized(&env)?;
@@ -447,16 +485,8 @@ impl AllocationStrategiesContract {
         let fn_symbol = symbol_short!("rebal");
         RateLimiter::check(&env, &caller, &fn_symbol);
 
-        // Verify ownership
-        let owner: Address = env
-            .storage()
-            .persistent()
-            .get(&DataKey::AllocationOwner(commitment_id))
-            .ok_or(Error::AllocationNotFound)?;
-
-        if owner != caller {
-            return Err(Error::Unauthorized);
-        }
+        // Verify ownership against core, which follows NFT transfers
+        Self::require_commitment_owner(&env, &caller, &commitment_id)?;
 
         // Check if contract is paused
         Pausable::require_not_paused(&env);
@@ -467,14 +497,14 @@ impl AllocationStrategiesContract {
         let current_allocations: Vec<Allocation> = env
             .storage()
             .persistent()
-            .get(&DataKey::Allocations(commitment_id))
+            .get(&DataKey::Allocations(commitment_id.clone()))
             .ok_or(Error::AllocationNotFound)?;
 
         // Get strategy
         let strategy: Strategy = env
             .storage()
             .persistent()
-            .get(&DataKey::Strategy(commitment_id))
+            .get(&DataKey::Strategy(commitment_id.clone()))
             .ok_or(Error::AllocationNotFound)?;
 
         let mut total_amount = 0i128;
@@ -527,7 +557,7 @@ impl AllocationStrategiesContract {
                     .set(&DataKey::Pool(pool_id), &pool);
 
                 let allocation = Allocation {
-                    commitment_id,
+                    commitment_id: commitment_id.clone(),
                     pool_id,
                     amount: alloc_amount,
                     timestamp: env.ledger().timestamp(),
@@ -540,17 +570,20 @@ impl AllocationStrategiesContract {
             }
         }
 
+        env.storage().persistent().set(
+            &DataKey::Allocations(commitment_id.clone()),
+            &new_allocations,
+        );
         env.storage()
             .persistent()
-            .set(&DataKey::Allocations(commitment_id), &new_allocations);
-        env.storage()
-            .persistent()
-            .set(&DataKey::TotalAllocated(commitment_id), &new_total);
+            .set(&DataKey::TotalAllocated(commitment_id.clone()), &new_total);
 
         Self::set_reentrancy_guard(&env, false);
 
-        env.events()
-            .publish((symbol_short!("rebalance"), commitment_id), new_total);
+        env.events().publish(
+            (symbol_short!("rebalance"), commitment_id.clone()),
+            new_total,
+        );
 
         Ok(AllocationSummary {
             commitment_id,
@@ -564,23 +597,23 @@ impl AllocationStrategiesContract {
     // VIEW FUNCTIONS
     // ========================================================================
 
-    pub fn get_allocation(env: Env, commitment_id: u64) -> AllocationSummary {
+    pub fn get_allocation(env: Env, commitment_id: String) -> AllocationSummary {
         let allocations: Vec<Allocation> = env
             .storage()
             .persistent()
-            .get(&DataKey::Allocations(commitment_id))
+            .get(&DataKey::Allocations(commitment_id.clone()))
             .unwrap_or(Vec::new(&env));
 
         let strategy: Strategy = env
             .storage()
             .persistent()
-            .get(&DataKey::Strategy(commitment_id))
+            .get(&DataKey::Strategy(commitment_id.clone()))
             .unwrap_or(Strategy::Balanced);
 
         let total = env
             .storage()
             .persistent()
-            .get(&DataKey::TotalAllocated(commitment_id))
+            .get(&DataKey::TotalAllocated(commitment_id.clone()))
             .unwrap_or(0i128);
 
         AllocationSummary {
@@ -674,6 +707,67 @@ impl AllocationStrategiesContract {
         Ok(())
     }
 
+    /// Move version 1 allocation records onto commitment_core's string ids (admin-only).
+    ///
+    /// Version 1 keyed allocations by a numeric id; `legacy_id` N is re-keyed to core's
+    /// `"c_N"`. Ids with no legacy record, or whose new key is already taken, are skipped.
+    /// Returns the number of records moved.
+    pub fn migrate_allocations(
+        env: Env,
+        caller: Address,
+        legacy_ids: Vec<u64>,
+    ) -> Result<u32, Error> {
+        caller.require_auth();
+        Self::require_initialized(&env)?;
+        Self::require_admin(&env, &caller)?;
+
+        let storage = env.storage().persistent();
+        let mut migrated = 0u32;
+
+        for legacy_id in legacy_ids.iter() {
+            let legacy: Vec<LegacyAllocation> =
+                match storage.get(&LegacyDataKey::Allocations(legacy_id)) {
+                    Some(allocations) => allocations,
+                    None => continue,
+                };
+            let commitment_id = commitment_id_for(&env, legacy_id);
+            if storage.has(&DataKey::Allocations(commitment_id.clone())) {
+                continue;
+            }
+
+            let mut allocations = Vec::new(&env);
+            for old in legacy.iter() {
+                allocations.push_back(Allocation {
+                    commitment_id: commitment_id.clone(),
+                    pool_id: old.pool_id,
+                    amount: old.amount,
+                    timestamp: old.timestamp,
+                });
+            }
+            storage.set(&DataKey::Allocations(commitment_id.clone()), &allocations);
+            storage.remove(&LegacyDataKey::Allocations(legacy_id));
+
+            if let Some(strategy) = storage.get::<_, Strategy>(&LegacyDataKey::Strategy(legacy_id))
+            {
+                storage.set(&DataKey::Strategy(commitment_id.clone()), &strategy);
+                storage.remove(&LegacyDataKey::Strategy(legacy_id));
+            }
+            if let Some(total) = storage.get::<_, i128>(&LegacyDataKey::TotalAllocated(legacy_id)) {
+                storage.set(&DataKey::TotalAllocated(commitment_id.clone()), &total);
+                storage.remove(&LegacyDataKey::TotalAllocated(legacy_id));
+            }
+            // Ownership is read from commitment_core now; the cached owner is dropped
+            storage.remove(&LegacyDataKey::AllocationOwner(legacy_id));
+
+            migrated += 1;
+        }
+
+        env.events()
+            .publish((symbol_short!("alloc_mig"), caller), migrated);
+
+        Ok(migrated)
+    }
+
     // ========================================================================
     // INTERNAL HELPER FUNCTIONS
     // ========================================================================
@@ -690,6 +784,39 @@ impl AllocationStrategiesContract {
         Ok(())
     }
 
+    fn commitment_core(env: &Env) -> Result<Address, Error> {
+        env.storage()
+            .instance()
+            .get(&DataKey::CommitmentCore)
+            .ok_or(Error::NotInitialized)
+    }
+
+    /// Look the commitment up in commitment_core and check that it is still open and that
+    /// `caller` owns it or is approved to allocate for the owner. Returns the commitment.
+    fn require_commitment_owner(
+        env: &Env,
+        caller: &Address,
+        commitment_id: &String,
+    ) -> Result<Commitment, Error> {
+        let core = Self::commitment_core(env)?;
+        let client = CommitmentClient::new(env, &core);
+
+        let commitment = match client.try_get_commitment(commitment_id) {
+            Ok(Ok(commitment)) => commitment,
+            _ => return Err(Error::CommitmentNotFound),
+        };
+
+        if commitment.owner != *caller
+            && !client.is_operator(commitment_id, caller, &OperatorScope::Allocate)
+        {
+            return Err(Error::Unauthorized);
+        }
+        if !commitment.status.is_open() {
+            return Err(Error::CommitmentNotActive);
+        }
+        Ok(commitment)
+    }
+
     fn require_admin(env: &Env, address: &Address) -> Result<(), Error> {
         let admin: Address = env
             .storage()
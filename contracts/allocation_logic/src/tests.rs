@@ -1,13 +1,128 @@
 // Comprehensive Security-Focused Tests
 #![cfg(test)]
 use crate::{
-    AllocationStrategiesContract, AllocationStrategiesContractClient, RiskLevel, Strategy,
+    AllocationStrategiesContract, AllocationStrategiesContractClient, LegacyAllocation,
+    LegacyDataKey, RiskLevel, Strategy,
 };
-use soroban_sdk::{testutils::Address as _, testutils::Ledger, Address, Env};
+use commitment_interface::types::{
+    commitment_id, Commitment, CommitmentRules, CommitmentStatus, CommitmentType, OperatorScope,
+    PenaltySchedule, ReleaseSchedule,
+};
+use commitment_interface::CommitmentInterface;
+use soroban_sdk::{
+    contract, contractimpl, testutils::Address as _, testutils::Ledger, vec, Address, Env, String,
+    Symbol, Vec,
+};
+
+/// Minimal commitment_core stand-in: stores commitments and serves them through `CommitmentInterface`.
+#[contract]
+pub struct MockCore;
+
+#[contractimpl]
+impl MockCore {
+    pub fn open(env: Env, owner: Address) -> String {
+        let n: u64 = env
+            .storage()
+            .instance()
+            .get(&Symbol::new(&env, "count"))
+            .unwrap_or(0);
+        let id = commitment_id(&env, n);
+        let commitment = Commitment {
+            commitment_id: id.clone(),
+            owner,
+            nft_token_id: n as u32,
+            rules: CommitmentRules {
+                duration_days: 30,
+                max_loss_percent: 10,
+                commitment_type: CommitmentType::Balanced,
+                early_exit_penalty: 5,
+                min_fee_threshold: 0,
+                grace_period_days: 0,
+                penalty_schedule: PenaltySchedule::Flat,
+                penalty_floor_percent: 0,
+                auto_rollover: false,
+                release_schedule: ReleaseSchedule::AtExpiry,
+            },
+            amount: 1_000_000_000,
+            asset_address: env.current_contract_address(),
+            created_at: env.ledger().timestamp(),
+            expires_at: env.ledger().timestamp() + 30 * 86400,
+            current_value: 1_000_000_000,
+            status: CommitmentStatus::Active,
+        };
+        env.storage().persistent().set(&id, &commitment);
+        env.storage()
+            .instance()
+            .set(&Symbol::new(&env, "count"), &(n + 1));
+        id
+    }
+
+    pub fn set_status(env: Env, commitment_id: String, status: CommitmentStatus) {
+        let mut commitment: Commitment = env.storage().persistent().get(&commitment_id).unwrap();
+        commitment.status = status;
+        env.storage().persistent().set(&commitment_id, &commitment);
+    }
+
+    /// Move the commitment to `owner`, as core does when its NFT is transferred.
+    pub fn set_owner(env: Env, commitment_id: String, owner: Address) {
+        let mut commitment: Commitment = env.storage().persistent().get(&commitment_id).unwrap();
+        commitment.owner = owner;
+        env.storage().persistent().set(&commitment_id, &commitment);
+    }
+
+    pub fn approve_allocator(env: Env, commitment_id: String, operator: Address) {
+        env.storage()
+            .persistent()
+            .set(&(commitment_id, operator), &true);
+    }
+}
+
+#[contractimpl]
+impl CommitmentInterface for MockCore {
+    fn get_commitment(env: Env, commitment_id: String) -> Commitment {
+        env.storage()
+            .persistent()
+            .get(&commitment_id)
+            .unwrap_or_else(|| panic!("Commitment not found"))
+    }
+
+    fn get_owner_commitments(env: Env, _owner: Address) -> Vec<String> {
+        Vec::new(&env)
+    }
+
+    fn get_total_commitments(env: Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, "count"))
+            .unwrap_or(0)
+    }
+
+    fn get_total_value_locked(_env: Env) -> i128 {
+        0
+    }
+
+    fn check_violations(_env: Env, _commitment_id: String) -> bool {
+        false
+    }
+
+    fn is_operator(
+        env: Env,
+        commitment_id: String,
+        operator: Address,
+        scope: OperatorScope,
+    ) -> bool {
+        scope == OperatorScope::Allocate
+            && env
+                .storage()
+                .persistent()
+                .get(&(commitment_id, operator))
+                .unwrap_or(false)
+    }
+}
 
 fn create_contract(env: &Env) -> (Address, Address, AllocationStrategiesContractClient<'_>) {
     let admin = Address::generate(env);
-    let commitment_core = Address::generate(env);
+    let commitment_core = env.register_contract(None, MockCore);
     let contract_id = env.register_contract(None, AllocationStrategiesContract);
     let client = AllocationStrategiesContractClient::new(env, &contract_id);
 
@@ -16,6 +131,11 @@ fn create_contract(env: &Env) -> (Address, Address, AllocationStrategiesContract
     (admin, commitment_core, client)
 }
 
+/// Open a commitment for `owner` in the mock core and return its id.
+fn open_commitment(env: &Env, core: &Address, owner: &Address) -> String {
+    MockCoreClient::new(env, core).open(owner)
+}
+
 fn setup_test_pools(_env: &Env, client: &AllocationStrategiesContractClient, admin: &Address) {
     client.register_pool(admin, &0, &RiskLevel::Low, &500, &1_000_000_000);
     client.register_pool(admin, &1, &RiskLevel::Low, &600, &1_000_000_000);
@@ -66,11 +186,11 @@ fn test_safe_strategy_allocation() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let (admin, _, client) = create_contract(&env);
+    let (admin, core, client) = create_contract(&env);
     setup_test_pools(&env, &client, &admin);
 
     let user = Address::generate(&env);
-    let commitment_id = 1u64;
+    let commitment_id = open_commitment(&env, &core, &user);
     let amount = 100_000_000i128;
 
     let summary = client.allocate(&user, &commitment_id, &amount, &Strategy::Safe);
@@ -91,11 +211,12 @@ fn test_balanced_strategy_allocation() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let (admin, _, client) = create_contract(&env);
+    let (admin, core, client) = create_contract(&env);
     setup_test_pools(&env, &client, &admin);
 
     let user = Address::generate(&env);
-    let summary = client.allocate(&user, &2, &100_000_000, &Strategy::Balanced);
+    let id = open_commitment(&env, &core, &user);
+    let summary = client.allocate(&user, &id, &100_000_000, &Strategy::Balanced);
 
     assert_eq!(summary.strategy, Strategy::Balanced);
 
@@ -116,11 +237,12 @@ fn test_aggressive_strategy_allocation() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let (admin, _, client) = create_contract(&env);
+    let (admin, core, client) = create_contract(&env);
     setup_test_pools(&env, &client, &admin);
 
     let user = Address::generate(&env);
-    let summary = client.allocate(&user, &3, &100_000_000, &Strategy::Aggressive);
+    let id = open_commitment(&env, &core, &user);
+    let summary = client.allocate(&user, &id, &100_000_000, &Strategy::Aggressive);
 
     assert_eq!(summary.strategy, Strategy::Aggressive);
 
@@ -136,17 +258,18 @@ fn test_get_allocation() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let (admin, _, client) = create_contract(&env);
+    let (admin, core, client) = create_contract(&env);
     setup_test_pools(&env, &client, &admin);
 
     let user = Address::generate(&env);
     let amount = 50_000_000i128;
 
-    client.allocate(&user, &4, &amount, &Strategy::Safe);
+    let id = open_commitment(&env, &core, &user);
+    client.allocate(&user, &id, &amount, &Strategy::Safe);
 
-    let summary = client.get_allocation(&4);
+    let summary = client.get_allocation(&id);
 
-    assert_eq!(summary.commitment_id, 4);
+    assert_eq!(summary.commitment_id, id);
     assert_eq!(summary.strategy, Strategy::Safe);
     assert_eq!(summary.total_allocated, amount);
 }
@@ -156,20 +279,21 @@ fn test_rebalance() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let (admin, _, client) = create_contract(&env);
+    let (admin, core, client) = create_contract(&env);
     setup_test_pools(&env, &client, &admin);
 
     let user = Address::generate(&env);
     let amount = 100_000_000i128;
 
     // Initial allocation
-    let _initial = client.allocate(&user, &5, &amount, &Strategy::Safe);
+    let id = open_commitment(&env, &core, &user);
+    let _initial = client.allocate(&user, &id, &amount, &Strategy::Safe);
 
     // Disable one of the pools
     client.update_pool_status(&admin, &0, &false);
 
     // Rebalance
-    let rebalanced = client.rebalance(&user, &5);
+    let rebalanced = client.rebalance(&user, &id);
 
     assert_eq!(rebalanced.strategy, Strategy::Safe);
 
@@ -200,7 +324,7 @@ fn test_pool_liquidity_tracking() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let (admin, _, client) = create_contract(&env);
+    let (admin, core, client) = create_contract(&env);
     setup_test_pools(&env, &client, &admin);
 
     let user = Address::generate(&env);
@@ -210,7 +334,8 @@ fn test_pool_liquidity_tracking() {
     assert_eq!(pool_before.total_liquidity, 0);
 
     // Allocate
-    client.allocate(&user, &1, &100_000_000, &Strategy::Safe);
+    let id = open_commitment(&env, &core, &user);
+    client.allocate(&user, &id, &100_000_000, &Strategy::Safe);
 
     // Check updated liquidity
     let pool_after = client.get_pool(&0);
@@ -225,12 +350,13 @@ fn test_allocation_timestamps() {
     // Set ledger timestamp
     env.ledger().set_timestamp(1000);
 
-    let (admin, _, client) = create_contract(&env);
+    let (admin, core, client) = create_contract(&env);
     setup_test_pools(&env, &client, &admin);
 
     let user = Address::generate(&env);
 
-    let summary = client.allocate(&user, &7, &100_000_000, &Strategy::Safe);
+    let id = open_commitment(&env, &core, &user);
+    let summary = client.allocate(&user, &id, &100_000_000, &Strategy::Safe);
 
     // All allocations should have timestamps
     for allocation in summary.allocations.iter() {
@@ -243,13 +369,14 @@ fn test_total_allocation_accuracy() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let (admin, _, client) = create_contract(&env);
+    let (admin, core, client) = create_contract(&env);
     setup_test_pools(&env, &client, &admin);
 
     let user = Address::generate(&env);
     let amount = 100_000_000i128;
 
-    let summary = client.allocate(&user, &8, &amount, &Strategy::Balanced);
+    let id = open_commitment(&env, &core, &user);
+    let summary = client.allocate(&user, &id, &amount, &Strategy::Balanced);
 
     // Sum all allocations
     let mut total = 0i128;
@@ -266,18 +393,21 @@ fn test_multiple_users_allocations() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let (admin, _, client) = create_contract(&env);
+    let (admin, core, client) = create_contract(&env);
     setup_test_pools(&env, &client, &admin);
 
     // Create multiple users and allocate
-    for i in 0..5 {
+    let mut ids = Vec::new(&env);
+    for _ in 0..5 {
         let user = Address::generate(&env);
-        client.allocate(&user, &(i + 10), &10_000_000, &Strategy::Balanced);
+        let id = open_commitment(&env, &core, &user);
+        client.allocate(&user, &id, &10_000_000, &Strategy::Balanced);
+        ids.push_back(id);
     }
 
     // Verify all allocations exist
-    for i in 0..5 {
-        let summary = client.get_allocation(&(i + 10));
+    for id in ids.iter() {
+        let summary = client.get_allocation(&id);
         assert_eq!(summary.total_allocated, 10_000_000);
     }
 }
@@ -288,7 +418,7 @@ fn test_allocation_rate_limit_enforced() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let (admin, _, client) = create_contract(&env);
+    let (admin, core, client) = create_contract(&env);
 
     // Configure rate limit: 1 allocation call per 60 seconds
     let fn_symbol = soroban_sdk::Symbol::new(&env, "alloc");
@@ -298,10 +428,12 @@ fn test_allocation_rate_limit_enforced() {
 
     // First allocation should succeed
     setup_test_pools(&env, &client, &admin);
-    client.allocate(&user, &100, &10_000_000, &Strategy::Balanced);
+    let first = open_commitment(&env, &core, &user);
+    client.allocate(&user, &first, &10_000_000, &Strategy::Balanced);
 
     // Second allocation should panic due to rate limit
-    client.allocate(&user, &101, &10_000_000, &Strategy::Balanced);
+    let second = open_commitment(&env, &core, &user);
+    client.allocate(&user, &second, &10_000_000, &Strategy::Balanced);
 }
 
 #[test]
@@ -311,7 +443,7 @@ fn test_get_nonexistent_allocation() {
 
     let (_, _, client) = create_contract(&env);
 
-    let summary = client.get_allocation(&999);
+    let summary = client.get_allocation(&String::from_str(&env, "c_999"));
 
     assert_eq!(summary.total_allocated, 0);
     assert_eq!(summary.allocations.len(), 0);
@@ -368,11 +500,12 @@ fn test_zero_amount_rejected() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let (admin, _, client) = create_contract(&env);
+    let (admin, core, client) = create_contract(&env);
     setup_test_pools(&env, &client, &admin);
 
     let user = Address::generate(&env);
-    client.allocate(&user, &1, &0, &Strategy::Safe);
+    let id = open_commitment(&env, &core, &user);
+    client.allocate(&user, &id, &0, &Strategy::Safe);
 }
 
 #[test]
@@ -415,12 +548,13 @@ fn test_pool_capacity_exceeded() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let (admin, _, client) = create_contract(&env);
+    let (admin, core, client) = create_contract(&env);
 
     client.register_pool(&admin, &0, &RiskLevel::Low, &500, &100_000);
 
     let user = Address::generate(&env);
-    client.allocate(&user, &1, &200_000, &Strategy::Safe);
+    let id = open_commitment(&env, &core, &user);
+    client.allocate(&user, &id, &200_000, &Strategy::Safe);
 }
 
 #[test]
@@ -429,13 +563,14 @@ fn test_double_allocation_prevented() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let (admin, _, client) = create_contract(&env);
+    let (admin, core, client) = create_contract(&env);
     setup_test_pools(&env, &client, &admin);
 
     let user = Address::generate(&env);
 
-    client.allocate(&user, &1, &100_000, &Strategy::Safe);
-    client.allocate(&user, &1, &50_000, &Strategy::Balanced);
+    let id = open_commitment(&env, &core, &user);
+    client.allocate(&user, &id, &100_000, &Strategy::Safe);
+    client.allocate(&user, &id, &50_000, &Strategy::Balanced);
 }
 
 #[test]
@@ -444,14 +579,15 @@ fn test_non_owner_cannot_rebalance() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let (admin, _, client) = create_contract(&env);
+    let (admin, core, client) = create_contract(&env);
     setup_test_pools(&env, &client, &admin);
 
     let user = Address::generate(&env);
     let other_user = Address::generate(&env);
 
-    client.allocate(&user, &1, &100_000_000, &Strategy::Safe);
-    client.rebalance(&other_user, &1);
+    let id = open_commitment(&env, &core, &user);
+    client.allocate(&user, &id, &100_000_000, &Strategy::Safe);
+    client.rebalance(&other_user, &id);
 }
 
 #[test]
@@ -460,11 +596,207 @@ fn test_no_active_pools_fails() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let (admin, _, client) = create_contract(&env);
+    let (admin, core, client) = create_contract(&env);
 
     client.register_pool(&admin, &0, &RiskLevel::Low, &500, &1_000_000_000);
     client.update_pool_status(&admin, &0, &false);
 
     let user = Address::generate(&env);
-    client.allocate(&user, &1, &100_000, &Strategy::Safe);
+    let id = open_commitment(&env, &core, &user);
+    client.allocate(&user, &id, &100_000, &Strategy::Safe);
+}
+
+// ============================================================================
+// COMMITMENT ID TESTS
+// ============================================================================
+
+#[test]
+#[should_panic(expected = "HostError: Error(Contract, #18)")]
+fn test_unknown_commitment_rejected() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (admin, _, client) = create_contract(&env);
+    setup_test_pools(&env, &client, &admin);
+
+    let user = Address::generate(&env);
+    client.allocate(
+        &user,
+        &String::from_str(&env, "c_42"),
+        &100_000,
+        &Strategy::Safe,
+    );
+}
+
+#[test]
+#[should_panic(expected = "HostError: Error(Contract, #3)")]
+fn test_allocate_requires_commitment_owner() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (admin, core, client) = create_contract(&env);
+    setup_test_pools(&env, &client, &admin);
+
+    let owner = Address::generate(&env);
+    let other_user = Address::generate(&env);
+    let id = open_commitment(&env, &core, &owner);
+
+    client.allocate(&other_user, &id, &100_000, &Strategy::Safe);
+}
+
+#[test]
+fn test_operator_allocates_and_rebalances_for_owner() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (admin, core, client) = create_contract(&env);
+    setup_test_pools(&env, &client, &admin);
+
+    let owner = Address::generate(&env);
+    let operator = Address::generate(&env);
+    let id = open_commitment(&env, &core, &owner);
+    MockCoreClient::new(&env, &core).approve_allocator(&id, &operator);
+
+    let summary = client.allocate(&operator, &id, &100_000, &Strategy::Balanced);
+    assert_eq!(summary.total_allocated, 100_000);
+
+    let rebalanced = client.rebalance(&operator, &id);
+    assert_eq!(rebalanced.total_allocated, 100_000);
+    assert!(client.try_rebalance(&Address::generate(&env), &id).is_err());
+}
+
+#[test]
+fn test_rebalance_follows_commitment_transfer() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (admin, core, client) = create_contract(&env);
+    setup_test_pools(&env, &client, &admin);
+
+    let seller = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let id = open_commitment(&env, &core, &seller);
+    client.allocate(&seller, &id, &100_000, &Strategy::Safe);
+    MockCoreClient::new(&env, &core).set_owner(&id, &buyer);
+
+    assert!(client.try_rebalance(&seller, &id).is_err());
+    let rebalanced = client.rebalance(&buyer, &id);
+    assert_eq!(rebalanced.total_allocated, 100_000);
+}
+
+#[test]
+#[should_panic(expected = "HostError: Error(Contract, #4)")]
+fn test_allocate_beyond_commitment_value_rejected() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (admin, core, client) = create_contract(&env);
+    setup_test_pools(&env, &client, &admin);
+
+    let user = Address::generate(&env);
+    let id = open_commitment(&env, &core, &user);
+    client.allocate(&user, &id, &1_000_000_001, &Strategy::Safe);
+}
+
+#[test]
+#[should_panic(expected = "HostError: Error(Contract, #19)")]
+fn test_allocate_rejects_closed_commitment() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (admin, core, client) = create_contract(&env);
+    setup_test_pools(&env, &client, &admin);
+
+    let user = Address::generate(&env);
+    let id = open_commitment(&env, &core, &user);
+    MockCoreClient::new(&env, &core).set_status(&id, &CommitmentStatus::Settled);
+
+    client.allocate(&user, &id, &100_000, &Strategy::Safe);
+}
+
+#[test]
+fn test_failed_validation_releases_guard() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (admin, core, client) = create_contract(&env);
+    setup_test_pools(&env, &client, &admin);
+
+    let user = Address::generate(&env);
+    let missing = String::from_str(&env, "c_42");
+    assert!(client
+        .try_allocate(&user, &missing, &100_000, &Strategy::Safe)
+        .is_err());
+
+    let id = open_commitment(&env, &core, &user);
+    let summary = client.allocate(&user, &id, &100_000, &Strategy::Safe);
+    assert_eq!(summary.total_allocated, 100_000);
+}
+
+#[test]
+fn test_migrate_allocations_rekeys_legacy_records() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (admin, _, client) = create_contract(&env);
+    let user = Address::generate(&env);
+
+    // Records as written by version 1, keyed by numeric id
+    env.as_contract(&client.address, || {
+        let storage = env.storage().persistent();
+        let legacy = vec![
+            &env,
+            LegacyAllocation {
+                commitment_id: 3,
+                pool_id: 0,
+                amount: 70_000,
+                timestamp: 5,
+            },
+            LegacyAllocation {
+                commitment_id: 3,
+                pool_id: 2,
+                amount: 30_000,
+                timestamp: 5,
+            },
+        ];
+        storage.set(&LegacyDataKey::Allocations(3), &legacy);
+        storage.set(&LegacyDataKey::Strategy(3), &Strategy::Aggressive);
+        storage.set(&LegacyDataKey::TotalAllocated(3), &100_000i128);
+        storage.set(&LegacyDataKey::AllocationOwner(3), &user);
+    });
+
+    // Id 9 has no legacy record and is skipped
+    let migrated = client.migrate_allocations(&admin, &vec![&env, 3u64, 9u64]);
+    assert_eq!(migrated, 1);
+
+    let id = String::from_str(&env, "c_3");
+    let summary = client.get_allocation(&id);
+    assert_eq!(summary.commitment_id, id);
+    assert_eq!(summary.strategy, Strategy::Aggressive);
+    assert_eq!(summary.total_allocated, 100_000);
+    assert_eq!(summary.allocations.len(), 2);
+    for allocation in summary.allocations.iter() {
+        assert_eq!(allocation.commitment_id, id);
+    }
+
+    env.as_contract(&client.address, || {
+        let storage = env.storage().persistent();
+        assert!(!storage.has(&LegacyDataKey::Allocations(3)));
+        assert!(!storage.has(&LegacyDataKey::AllocationOwner(3)));
+    });
+
+    // Running it again finds nothing left to move
+    assert_eq!(client.migrate_allocations(&admin, &vec![&env, 3u64]), 0);
+}
+
+#[test]
+#[should_panic(expected = "HostError: Error(Contract, #3)")]
+fn test_non_admin_cannot_migrate_allocations() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (_, _, client) = create_contract(&env);
+    let attacker = Address::generate(&env);
+
+    client.migrate_allocations(&attacker, &vec![&env, 1u64]);
 }
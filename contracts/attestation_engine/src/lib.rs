@@ -1,8 +1,12 @@
 #![no_std]
+pub use commitment_interface::types::{
+    Commitment, CommitmentRules, CommitmentStatus, CommitmentType, PenaltySchedule, PenaltyStep,
+};
+use commitment_interface::CommitmentClient;
 use shared_utils::{BatchError, BatchMode, BatchProcessor, BatchResultVoid, Pausable, RateLimiter};
 use soroban_sdk::{
     contract, contracterror, contractimpl, contracttype, symbol_short, token, Address, BytesN, Env,
-    IntoVal, Map, String, Symbol, TryIntoVal, Val, Vec,
+    Map, String, Symbol, Vec,
 };
 
 const CURRENT_VERSION: u32 = 1;
@@ -108,34 +112,6 @@ pub struct AttestParams {
     pub is_compliant: bool,
 }
 
-// Import Commitment types from commitment_core (define locally for cross-contract calls)
-#[contracttype]
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub struct CommitmentRules {
-    pub duration_days: u32,
-    pub max_loss_percent: u32,
-    pub commitment_type: String, // "safe", "balanced", "aggressive"
-    pub early_exit_penalty: u32,
-    pub min_fee_threshold: i128,
-    pub grace_period_days: u32,
-}
-
-#[contracttype]
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub struct Commitment {
-    pub commitment_id: String,
-    pub owner: Address,
-    pub nft_token_id: u32,
-    pub rules: CommitmentRules,
-    pub amount: i128,
-    pub asset_address: Address,
-    pub created_at: u64,
-    pub expires_at: u64,
-    pub current_value: i128,
-    pub status: String, // "active", "settled", "violated", "early_exit"
-}
-
-
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct HealthMetrics {
@@ -468,16 +444,8 @@ impl AttestationEngineContract {
             None => return false,
         };
 
-        // Try to get commitment from core contract
-        let mut args = Vec::new(e);
-        args.push_back(commitment_id.clone().into_val(e));
-
-        // Use try_invoke_contract to handle potential failures
-        let result = e.try_invoke_contract::<Val, soroban_sdk::Error>(
-            &commitment_core,
-            &Symbol::new(e, "get_commitment"),
-            args,
-        );
+        // Try to get commitment from core contract; a missing commitment makes the call fail
+        let result = CommitmentClient::new(e, &commitment_core).try_get_commitment(commitment_id);
 
         match result {
             Ok(Ok(_)) => true,
@@ -815,15 +783,8 @@ impl AttestationEngineContract {
             .get(&DataKey::CoreContract)
             .unwrap_or_else(|| panic!("Contract not initialized"));
 
-        // Call get_commitment on commitment_core contract
-        // Using Symbol::new() for function name longer than 9 characters
-        let mut args = Vec::new(&e);
-        args.push_back(commitment_id.clone().into_val(&e));
-        let commitment_val: Val =
-            e.invoke_contract(&commitment_core, &Symbol::new(&e, "get_commitment"), args);
-
-        // Convert Val to Commitment
-        let commitment: Commitment = commitment_val.try_into_val(&e).unwrap();
+        // Get commitment from core contract
+        let commitment = CommitmentClient::new(&e, &commitment_core).get_commitment(&commitment_id);
 
         // Get all attestations
         let attestations = Self::get_attestations(e.clone(), commitment_id.clone());
@@ -929,21 +890,11 @@ impl AttestationEngineContract {
         };
 
         // Get commitment details
-        let mut args = Vec::new(&e);
-        args.push_back(commitment_id.clone().into_val(&e));
-        let commitment_val: Val = match e.try_invoke_contract::<Val, soroban_sdk::Error>(
-            &commitment_core,
-            &Symbol::new(&e, "get_commitment"),
-            args,
-        ) {
-            Ok(Ok(val)) => val,
-            _ => return false,
-        };
-
-        let commitment: Commitment = match commitment_val.try_into_val(&e) {
-            Ok(c) => c,
-            Err(_) => return false,
-        };
+        let commitment =
+            match CommitmentClient::new(&e, &commitment_core).try_get_commitment(&commitment_id) {
+                Ok(Ok(c)) => c,
+                _ => return false,
+            };
 
         // Get health metrics
         let metrics = Self::get_health_metrics(e.clone(), commitment_id);
@@ -1023,13 +974,9 @@ impl AttestationEngineContract {
             .get(&DataKey::CoreContract)
             .ok_or(AttestationError::NotInitialized)?;
 
-        let mut args = Vec::new(&e);
-        args.push_back(commitment_id.clone().into_val(&e));
-        let commitment_val: Val =
-            e.invoke_contract(&commitment_core, &Symbol::new(&e, "get_commitment"), args);
-
-        let commitment: Commitment = commitment_val
-            .try_into_val(&e)
+        let commitment = CommitmentClient::new(&e, &commitment_core)
+            .try_get_commitment(&commitment_id)
+            .map_err(|_| AttestationError::CommitmentNotFound)?
             .map_err(|_| AttestationError::CommitmentNotFound)?;
 
         let max_loss = commitment.rules.max_loss_percent as i128;
@@ -1122,15 +1069,8 @@ impl AttestationEngineContract {
         // Get commitment from core contract
         let commitment_core: Address = e.storage().instance().get(&DataKey::CoreContract).unwrap();
 
-        // Call get_commitment on commitment_core contract
-        // Using Symbol::new() for function name longer than 9 characters
-        let mut args = Vec::new(&e);
-        args.push_back(commitment_id.clone().into_val(&e));
-        let commitment_val: Val =
-            e.invoke_contract(&commitment_core, &Symbol::new(&e, "get_commitment"), args);
-
-        // Convert Val to Commitment
-        let commitment: Commitment = commitment_val.try_into_val(&e).unwrap();
+        // Get commitment from core contract
+        let commitment = CommitmentClient::new(&e, &commitment_core).get_commitment(&commitment_id);
 
         // Get all attestations
         let attestations = Self::get_attestations(e.clone(), commitment_id.clone());
@@ -1253,14 +1193,7 @@ impl AttestationEngineContract {
         // Read commitment_core statistics
         let commitment_core: Address = e.storage().instance().get(&DataKey::CoreContract).unwrap();
 
-        // get_total_commitments() on core contract
-        let args = Vec::new(&e);
-        let total_commitments_val: Val = e.invoke_contract(
-            &commitment_core,
-            &Symbol::new(&e, "get_total_commitments"),
-            args,
-        );
-        let total_commitments: u64 = total_commitments_val.try_into_val(&e).unwrap();
+        let total_commitments = CommitmentClient::new(&e, &commitment_core).get_total_commitments();
 
         let total_attestations: u64 = e
             .storage()
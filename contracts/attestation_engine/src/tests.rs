@@ -2,7 +2,9 @@
 
 use super::*;
 use commitment_core::{
-    Commitment as CoreCommitment, CommitmentRules as CoreCommitmentRules, DataKey,
+    Commitment as CoreCommitment, CommitmentRules as CoreCommitmentRules,
+    CommitmentStatus as CoreCommitmentStatus, CommitmentType as CoreCommitmentType, DataKey,
+    PenaltySchedule as CorePenaltySchedule, PenaltyStep as CorePenaltyStep, ReleaseSchedule,
 };
 use soroban_sdk::{
     contract, contractimpl, contracttype, symbol_short, testutils::Address as _,
@@ -56,17 +58,44 @@ fn core_commitment_from_engine(c: Commitment) -> CoreCommitment {
         rules: CoreCommitmentRules {
             duration_days: c.rules.duration_days,
             max_loss_percent: c.rules.max_loss_percent,
-            commitment_type: c.rules.commitment_type,
+            commitment_type: match c.rules.commitment_type {
+                CommitmentType::Safe => CoreCommitmentType::Safe,
+                CommitmentType::Balanced => CoreCommitmentType::Balanced,
+                CommitmentType::Aggressive => CoreCommitmentType::Aggressive,
+            },
             early_exit_penalty: c.rules.early_exit_penalty,
             min_fee_threshold: c.rules.min_fee_threshold,
             grace_period_days: c.rules.grace_period_days,
+            penalty_schedule: match c.rules.penalty_schedule {
+                PenaltySchedule::Flat => CorePenaltySchedule::Flat,
+                PenaltySchedule::Linear => CorePenaltySchedule::Linear,
+                PenaltySchedule::Steps(steps) => {
+                    let mut core_steps = Vec::new(steps.env());
+                    for step in steps.iter() {
+                        core_steps.push_back(CorePenaltyStep {
+                            until_elapsed_percent: step.until_elapsed_percent,
+                            penalty_percent: step.penalty_percent,
+                        });
+                    }
+                    CorePenaltySchedule::Steps(core_steps)
+                }
+            },
+            penalty_floor_percent: c.rules.penalty_floor_percent,
+            auto_rollover: c.rules.auto_rollover,
+            release_schedule: c.rules.release_schedule,
         },
         amount: c.amount,
         asset_address: c.asset_address,
         created_at: c.created_at,
         expires_at: c.expires_at,
         current_value: c.current_value,
-        status: c.status,
+        status: match c.status {
+            CommitmentStatus::Active => CoreCommitmentStatus::Active,
+            CommitmentStatus::AtRisk => CoreCommitmentStatus::AtRisk,
+            CommitmentStatus::Settled => CoreCommitmentStatus::Settled,
+            CommitmentStatus::Violated => CoreCommitmentStatus::Violated,
+            CommitmentStatus::EarlyExit => CoreCommitmentStatus::EarlyExit,
+        },
     }
 }
 
@@ -95,21 +124,25 @@ fn store_core_commitment(
         rules: CoreCommitmentRules {
             duration_days,
             max_loss_percent,
-            commitment_type: String::from_str(e, "balanced"),
+            commitment_type: CoreCommitmentType::Balanced,
             early_exit_penalty: 10,
             min_fee_threshold: 1000,
             grace_period_days: 3,
+            penalty_schedule: CorePenaltySchedule::Flat,
+            penalty_floor_percent: 0,
+            auto_rollover: false,
+            release_schedule: ReleaseSchedule::AtExpiry,
         },
         amount,
         asset_address: Address::generate(e),
         created_at,
         expires_at,
         current_value,
-        status: String::from_str(e, "active"),
+        status: CoreCommitmentStatus::Active,
     };
 
     e.as_contract(commitment_core_id, || {
-        e.storage().instance().set(
+        e.storage().persistent().set(
             &DataKey::Commitment(commitment.commitment_id.clone()),
             &commitment,
         );
@@ -154,10 +187,14 @@ fn test_attest() {
     let rules = CommitmentRules {
         duration_days: 10,
         max_loss_percent: 20,
-        commitment_type: String::from_str(&e, "safe"),
+        commitment_type: CommitmentType::Safe,
         early_exit_penalty: 0,
         min_fee_threshold: 0,
         grace_period_days: 0,
+        penalty_schedule: PenaltySchedule::Flat,
+        penalty_floor_percent: 0,
+        auto_rollover: false,
+        release_schedule: ReleaseSchedule::AtExpiry,
     };
     let commitment = Commitment {
         commitment_id: commitment_id.clone(),
@@ -169,7 +206,7 @@ fn test_attest() {
         created_at: 0,
         expires_at: 100,
         current_value: 1_000,
-        status: String::from_str(&e, "active"),
+        status: CommitmentStatus::Active,
     };
 
     e.as_contract(&core_id, || {
@@ -219,10 +256,14 @@ fn test_verify_compliance() {
     let base_rules = CommitmentRules {
         duration_days: 10,
         max_loss_percent: 20,
-        commitment_type: String::from_str(&e, "safe"),
+        commitment_type: CommitmentType::Safe,
         early_exit_penalty: 0,
         min_fee_threshold: 100,
         grace_period_days: 0,
+        penalty_schedule: PenaltySchedule::Flat,
+        penalty_floor_percent: 0,
+        auto_rollover: false,
+        release_schedule: ReleaseSchedule::AtExpiry,
     };
 
     // Happy path: in-range drawdown, not expired, fees meet threshold, no violations.
@@ -236,7 +277,7 @@ fn test_verify_compliance() {
         created_at: 0,
         expires_at: 100,
         current_value: 900, // 10% drawdown
-        status: String::from_str(&e, "active"),
+        status: CommitmentStatus::Active,
     };
     e.as_contract(&core_id, || {
         MockCoreContract::set_commitment(e.clone(), commitment_id.clone(), commitment.clone());
@@ -318,6 +359,10 @@ fn test_verify_compliance() {
     let rules_no_duration = CommitmentRules {
         duration_days: 0,
         grace_period_days: 0,
+        penalty_schedule: PenaltySchedule::Flat,
+        penalty_floor_percent: 0,
+        auto_rollover: false,
+        release_schedule: ReleaseSchedule::AtExpiry,
         ..base_rules
     };
     let commitment3 = Commitment {
@@ -330,7 +375,7 @@ fn test_verify_compliance() {
         created_at: 0,
         expires_at: 0,
         current_value: 0,
-        status: String::from_str(&e, "active"),
+        status: CommitmentStatus::Active,
     };
     e.as_contract(&core_id, || {
         MockCoreContract::set_commitment(e.clone(), commitment_id3.clone(), commitment3);
@@ -83,10 +83,14 @@ fn benchmark_create_commitment() {
     let rules = CommitmentRules {
         duration_days: 30,
         max_loss_percent: 20,
-        commitment_type: String::from_str(&e, "balanced"),
+        commitment_type: CommitmentType::Balanced,
         early_exit_penalty: 10,
         min_fee_threshold: 1000,
         grace_period_days: 0,
+        penalty_schedule: PenaltySchedule::Flat,
+        penalty_floor_percent: 0,
+        auto_rollover: false,
+        release_schedule: ReleaseSchedule::AtExpiry,
     };
 
     let mut metrics = BenchmarkMetrics::new("create_commitment");
@@ -117,10 +121,14 @@ fn benchmark_get_commitment() {
     let rules = CommitmentRules {
         duration_days: 30,
         max_loss_percent: 20,
-        commitment_type: String::from_str(&e, "balanced"),
+        commitment_type: CommitmentType::Balanced,
         early_exit_penalty: 10,
         min_fee_threshold: 1000,
         grace_period_days: 0,
+        penalty_schedule: PenaltySchedule::Flat,
+        penalty_floor_percent: 0,
+        auto_rollover: false,
+        release_schedule: ReleaseSchedule::AtExpiry,
     };
 
     let commitment_id = e.as_contract(&contract_id, || {
@@ -155,10 +163,14 @@ fn benchmark_check_violations() {
     let rules = CommitmentRules {
         duration_days: 30,
         max_loss_percent: 20,
-        commitment_type: String::from_str(&e, "balanced"),
+        commitment_type: CommitmentType::Balanced,
         early_exit_penalty: 10,
         min_fee_threshold: 1000,
         grace_period_days: 0,
+        penalty_schedule: PenaltySchedule::Flat,
+        penalty_floor_percent: 0,
+        auto_rollover: false,
+        release_schedule: ReleaseSchedule::AtExpiry,
     };
 
     let commitment_id = e.as_contract(&contract_id, || {
@@ -226,10 +238,14 @@ fn benchmark_batch_create_commitments() {
     let rules = CommitmentRules {
         duration_days: 30,
         max_loss_percent: 20,
-        commitment_type: String::from_str(&e, "balanced"),
+        commitment_type: CommitmentType::Balanced,
         early_exit_penalty: 10,
         min_fee_threshold: 1000,
         grace_period_days: 0,
+        penalty_schedule: PenaltySchedule::Flat,
+        penalty_floor_percent: 0,
+        auto_rollover: false,
+        release_schedule: ReleaseSchedule::AtExpiry,
     };
 
     let mut metrics = BenchmarkMetrics::new("batch_create_commitments_10");
@@ -36,10 +36,14 @@ fn benchmark_create_commitment_storage_reads() {
     let rules = CommitmentRules {
         duration_days: 30,
         max_loss_percent: 20,
-        commitment_type: String::from_str(&env, "balanced"),
+        commitment_type: CommitmentType::Balanced,
         early_exit_penalty: 10,
         min_fee_threshold: 1000,
         grace_period_days: 0,
+        penalty_schedule: PenaltySchedule::Flat,
+        penalty_floor_percent: 0,
+        auto_rollover: false,
+        release_schedule: ReleaseSchedule::AtExpiry,
     };
     
     // Measure CPU and memory before
@@ -71,10 +75,14 @@ fn benchmark_batch_counter_updates() {
     let rules = CommitmentRules {
         duration_days: 30,
         max_loss_percent: 20,
-        commitment_type: String::from_str(&env, "balanced"),
+        commitment_type: CommitmentType::Balanced,
         early_exit_penalty: 10,
         min_fee_threshold: 1000,
         grace_period_days: 0,
+        penalty_schedule: PenaltySchedule::Flat,
+        penalty_floor_percent: 0,
+        auto_rollover: false,
+        release_schedule: ReleaseSchedule::AtExpiry,
     };
     
     // Create multiple commitments to test counter updates
@@ -128,10 +136,14 @@ fn benchmark_check_violations() {
     let rules = CommitmentRules {
         duration_days: 30,
         max_loss_percent: 20,
-        commitment_type: String::from_str(&env, "balanced"),
+        commitment_type: CommitmentType::Balanced,
         early_exit_penalty: 10,
         min_fee_threshold: 1000,
         grace_period_days: 0,
+        penalty_schedule: PenaltySchedule::Flat,
+        penalty_floor_percent: 0,
+        auto_rollover: false,
+        release_schedule: ReleaseSchedule::AtExpiry,
     };
     
     let commitment_id = client.create_commitment(&owner, &10000, &asset, &rules);
@@ -214,10 +226,14 @@ fn benchmark_settle_function() {
     let rules = CommitmentRules {
         duration_days: 1, // Short duration for testing
         max_loss_percent: 20,
-        commitment_type: String::from_str(&env, "balanced"),
+        commitment_type: CommitmentType::Balanced,
         early_exit_penalty: 10,
         min_fee_threshold: 1000,
         grace_period_days: 0,
+        penalty_schedule: PenaltySchedule::Flat,
+        penalty_floor_percent: 0,
+        auto_rollover: false,
+        release_schedule: ReleaseSchedule::AtExpiry,
     };
     
     let commitment_id = client.create_commitment(&owner, &10000, &asset, &rules);
@@ -254,10 +270,14 @@ fn benchmark_memory_usage() {
     let rules = CommitmentRules {
         duration_days: 30,
         max_loss_percent: 20,
-        commitment_type: String::from_str(&env, "balanced"),
+        commitment_type: CommitmentType::Balanced,
         early_exit_penalty: 10,
         min_fee_threshold: 1000,
         grace_period_days: 0,
+        penalty_schedule: PenaltySchedule::Flat,
+        penalty_floor_percent: 0,
+        auto_rollover: false,
+        release_schedule: ReleaseSchedule::AtExpiry,
     };
     
     env.budget().reset_unlimited();
@@ -1,7 +1,8 @@
 #![cfg(test)]
 
 use super::*;
-use soroban_sdk::{testutils::Address as _, Address, Env, String};
+use crate::tests::{basket_legs, default_rules, setup_basket, setup_with_token, TestSetup};
+use soroban_sdk::{contract, contractimpl, testutils::Address as _, token, Address, Env, String};
 
 #[test]
 fn test_emergency_mode_toggle() {
@@ -43,10 +44,14 @@ fn test_create_commitment_forbidden_in_emergency() {
     let rules = CommitmentRules {
         duration_days: 30,
         max_loss_percent: 10,
-        commitment_type: String::from_str(&e, "safe"),
+        commitment_type: CommitmentType::Safe,
         early_exit_penalty: 5,
         min_fee_threshold: 100,
         grace_period_days: 0,
+        penalty_schedule: PenaltySchedule::Flat,
+        penalty_floor_percent: 0,
+        auto_rollover: false,
+        release_schedule: ReleaseSchedule::AtExpiry,
     };
 
     // This should panic because of emergency mode
@@ -62,13 +67,11 @@ fn test_emergency_withdraw_forbidden_in_normal_mode() {
     let client = CommitmentCoreContractClient::new(&e, &contract_id);
     let admin = Address::generate(&e);
     let nft_contract = Address::generate(&e);
-    let to = Address::generate(&e);
-    let asset = Address::generate(&e);
 
     client.initialize(&admin, &nft_contract);
 
     // Normal mode, should panic
-    client.emergency_withdraw(&admin, &asset, &to, &1000);
+    client.emergency_withdraw(&String::from_str(&e, "c_0"), &admin);
 }
 
 #[test]
@@ -87,3 +90,256 @@ fn test_set_emergency_mode_unauthorized() {
     // Using attacker address should fail the require_admin check
     client.set_emergency_mode(&attacker, &true);
 }
+
+/// Create a 1000 commitment in the setup's token and turn emergency mode on.
+fn commit_then_emergency(s: &TestSetup) -> String {
+    let id = s
+        .client
+        .create_commitment(&s.owner, &1000, &s.token.address, &default_rules(&s.e));
+    s.client.set_emergency_mode(&s.admin, &true);
+    id
+}
+
+#[test]
+fn test_emergency_withdraw_returns_value_without_penalty() {
+    let s = setup_with_token(10_000);
+    let id = commit_then_emergency(&s);
+
+    assert_eq!(s.client.emergency_withdraw(&id, &s.owner), 1000);
+
+    let commitment = s.client.get_commitment(&id);
+    assert_eq!(commitment.status, CommitmentStatus::EarlyExit);
+    assert_eq!(commitment.current_value, 0);
+    assert_eq!(s.token.balance(&s.owner), 10_000);
+    assert_eq!(s.client.get_total_value_locked(), 0);
+    assert_eq!(s.client.get_tvl_by_asset(&s.token.address), 0);
+    assert_eq!(s.client.get_penalty_account(&s.token.address).collected, 0);
+    assert!(s.nft.is_settled(&commitment.nft_token_id));
+}
+
+#[test]
+fn test_emergency_withdraw_by_admin_pays_owner() {
+    let s = setup_with_token(10_000);
+    let id = commit_then_emergency(&s);
+
+    assert_eq!(s.client.emergency_withdraw(&id, &s.admin), 1000);
+    assert_eq!(s.token.balance(&s.owner), 10_000);
+    assert_eq!(s.token.balance(&s.admin), 0);
+}
+
+#[test]
+#[should_panic(expected = "Unauthorized: caller not allowed")]
+fn test_emergency_withdraw_unauthorized() {
+    let s = setup_with_token(10_000);
+    let id = commit_then_emergency(&s);
+
+    s.client.emergency_withdraw(&id, &Address::generate(&s.e));
+}
+
+#[test]
+#[should_panic(expected = "Commitment is not active")]
+fn test_emergency_withdraw_twice() {
+    let s = setup_with_token(10_000);
+    let id = commit_then_emergency(&s);
+
+    s.client.emergency_withdraw(&id, &s.owner);
+    s.client.emergency_withdraw(&id, &s.owner);
+}
+
+#[test]
+#[should_panic(expected = "Commitment not found")]
+fn test_emergency_withdraw_unknown_commitment() {
+    let s = setup_with_token(10_000);
+    s.client.set_emergency_mode(&s.admin, &true);
+
+    s.client
+        .emergency_withdraw(&String::from_str(&s.e, "c_99"), &s.owner);
+}
+
+#[test]
+fn test_emergency_withdraw_basket_returns_every_leg() {
+    let s = setup_with_token(10_000);
+    let (_oracle, second) = setup_basket(&s);
+    let legs = basket_legs(&s, &second);
+    let id =
+        s.client
+            .create_basket_commitment(&s.owner, &s.token.address, &legs, &default_rules(&s.e));
+    s.client.set_emergency_mode(&s.admin, &true);
+
+    assert_eq!(s.client.emergency_withdraw(&id, &s.owner), 1000);
+    assert_eq!(s.token.balance(&s.owner), 10_000);
+    assert_eq!(second.balance(&s.owner), 1000);
+    assert_eq!(s.client.get_tvl_by_asset(&s.token.address), 0);
+    assert_eq!(s.client.get_tvl_by_asset(&second.address), 0);
+}
+
+#[test]
+fn test_emergency_withdraw_pays_funder_during_clawback() {
+    let s = setup_with_token(10_000);
+    let beneficiary = Address::generate(&s.e);
+    let id = s.client.create_commitment_for(
+        &s.owner,
+        &beneficiary,
+        &1000,
+        &s.token.address,
+        &default_rules(&s.e),
+        &Some(s.e.ledger().timestamp() + 86400),
+    );
+    s.client.set_emergency_mode(&s.admin, &true);
+
+    assert_eq!(s.client.emergency_withdraw(&id, &beneficiary), 1000);
+    assert_eq!(s.token.balance(&s.owner), 10_000);
+    assert_eq!(s.token.balance(&beneficiary), 0);
+}
+
+/// NFT contract whose `settle` always fails, as commitment_nft does in emergency mode.
+#[contract]
+pub struct FailingSettleNft;
+
+#[contractimpl]
+impl FailingSettleNft {
+    #[allow(clippy::too_many_arguments)]
+    pub fn mint(
+        _e: Env,
+        _owner: Address,
+        _commitment_id: String,
+        _duration_days: u32,
+        _max_loss_percent: u32,
+        _commitment_type: String,
+        _initial_amount: i128,
+        _asset_address: Address,
+    ) -> u32 {
+        0
+    }
+
+    pub fn settle(_e: Env, _caller: Address, _token_id: u32) {
+        panic!("Action not allowed in emergency mode");
+    }
+}
+
+#[test]
+fn test_emergency_withdraw_not_blocked_by_nft() {
+    let e = Env::default();
+    e.mock_all_auths_allowing_non_root_auth();
+    let client =
+        CommitmentCoreContractClient::new(&e, &e.register_contract(None, CommitmentCoreContract));
+    let admin = Address::generate(&e);
+    let owner = Address::generate(&e);
+    let token_id = e
+        .register_stellar_asset_contract_v2(Address::generate(&e))
+        .address();
+    token::StellarAssetClient::new(&e, &token_id).mint(&owner, &1000);
+    client.initialize(&admin, &e.register_contract(None, FailingSettleNft));
+
+    let id = client.create_commitment(&owner, &1000, &token_id, &default_rules(&e));
+    client.set_emergency_mode(&admin, &true);
+
+    assert_eq!(client.emergency_withdraw(&id, &owner), 1000);
+    assert_eq!(token::Client::new(&e, &token_id).balance(&owner), 1000);
+}
+
+#[test]
+#[should_panic(expected = "Action not allowed in emergency mode")]
+fn test_create_basket_commitment_forbidden_in_emergency() {
+    let s = setup_with_token(10_000);
+    let (_oracle, second) = setup_basket(&s);
+    let legs = basket_legs(&s, &second);
+    s.client.set_emergency_mode(&s.admin, &true);
+
+    s.client
+        .create_basket_commitment(&s.owner, &s.token.address, &legs, &default_rules(&s.e));
+}
+
+#[test]
+#[should_panic(expected = "Action not allowed in emergency mode")]
+fn test_allocate_forbidden_in_emergency() {
+    let s = setup_with_token(10_000);
+    let id = commit_then_emergency(&s);
+
+    s.client
+        .allocate(&id, &s.owner, &Address::generate(&s.e), &100);
+}
+
+#[test]
+#[should_panic(expected = "Action not allowed in emergency mode")]
+fn test_top_up_forbidden_in_emergency() {
+    let s = setup_with_token(10_000);
+    let id = commit_then_emergency(&s);
+
+    s.client.top_up(&id, &s.owner, &100);
+}
+
+#[test]
+#[should_panic(expected = "Action not allowed in emergency mode")]
+fn test_renew_forbidden_in_emergency() {
+    let s = setup_with_token(10_000);
+    let id = commit_then_emergency(&s);
+
+    s.client.renew(&id, &s.owner, &default_rules(&s.e));
+}
+
+#[test]
+fn test_emergency_sweep_takes_only_dust() {
+    let s = setup_with_token(10_000);
+    commit_then_emergency(&s);
+    // Sent straight to the contract, not part of any commitment
+    s.token.transfer(&s.owner, &s.client.address, &25);
+    let to = Address::generate(&s.e);
+
+    assert_eq!(
+        s.client.emergency_sweep(&s.admin, &s.token.address, &to),
+        25
+    );
+    assert_eq!(s.token.balance(&to), 25);
+    assert_eq!(s.token.balance(&s.client.address), 1000);
+
+    assert_eq!(s.client.emergency_sweep(&s.admin, &s.token.address, &to), 0);
+}
+
+#[test]
+fn test_emergency_sweep_keeps_penalties_owed() {
+    let s = setup_with_token(10_000);
+    s.client
+        .set_penalty_destination(&s.admin, &PenaltyDestination::Redistribute);
+    let staying =
+        s.client
+            .create_commitment(&s.owner, &1000, &s.token.address, &default_rules(&s.e));
+    let exiting =
+        s.client
+            .create_commitment(&s.owner, &1000, &s.token.address, &default_rules(&s.e));
+    s.client.early_exit(&exiting, &s.owner); // 10% penalty = 100, credited to `staying`
+    s.client
+        .set_penalty_destination(&s.admin, &PenaltyDestination::Treasury);
+    let exiting =
+        s.client
+            .create_commitment(&s.owner, &1000, &s.token.address, &default_rules(&s.e));
+    s.client.early_exit(&exiting, &s.owner); // 100 held for the treasury
+    s.client.set_emergency_mode(&s.admin, &true);
+    let to = Address::generate(&s.e);
+
+    assert_eq!(s.token.balance(&s.client.address), 1200);
+    assert_eq!(s.client.emergency_sweep(&s.admin, &s.token.address, &to), 0);
+
+    assert_eq!(s.client.claim_penalty_share(&staying, &s.owner), 100);
+    assert_eq!(s.client.emergency_sweep(&s.admin, &s.token.address, &to), 0);
+    assert_eq!(s.token.balance(&s.client.address), 1100);
+}
+
+#[test]
+#[should_panic(expected = "Action only allowed in emergency mode")]
+fn test_emergency_sweep_forbidden_in_normal_mode() {
+    let s = setup_with_token(10_000);
+
+    s.client
+        .emergency_sweep(&s.admin, &s.token.address, &s.admin);
+}
+
+#[test]
+#[should_panic(expected = "Unauthorized: caller not allowed")]
+fn test_emergency_sweep_unauthorized() {
+    let s = setup_with_token(10_000);
+    s.client.set_emergency_mode(&s.admin, &true);
+
+    s.client
+        .emergency_sweep(&s.owner, &s.token.address, &s.owner);
+}
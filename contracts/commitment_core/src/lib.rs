@@ -1,9 +1,18 @@
 #![no_std]
 
-use shared_utils::{emit_error_event, Pausable, RateLimiter, SafeMath, TimeUtils, Validation};
+pub use commitment_interface::types::{
+    Commitment, CommitmentRules, CommitmentStatus, CommitmentType, OperatorScope, PenaltySchedule,
+    PenaltyStep, ReleaseSchedule,
+};
+use commitment_interface::{AllocationPoolClient, CommitmentInterface};
+use shared_utils::fees::{fee_from_bps, BPS_MAX};
+use shared_utils::{
+    emit_error_event, BatchError, BatchProcessor, BatchResultVoid, EmergencyControl, Pausable,
+    RateLimiter, SafeMath, TimeUtils, Validation,
+};
 use soroban_sdk::{
-    contract, contracterror, contractimpl, contracttype, log, symbol_short, token, Address, Env,
-    IntoVal, String, Symbol, Vec,
+    contract, contracterror, contractimpl, contracttype, log, symbol_short, token, Address, BytesN,
+    Env, Error, IntoVal, Map, String, Symbol, TryFromVal, Val, Vec,
 };
 
 #[contracterror]
@@ -27,6 +36,37 @@ pub enum CommitmentError {
     NotExpired = 15,
     ValueUpdateViolation = 16,
     NotAuthorizedUpdater = 17,
+    OracleNotConfigured = 18,
+    StalePrice = 19,
+    PriceUnavailable = 20,
+    AlreadyMigrated = 21,
+    InvalidFeeBps = 22,
+    TreasuryNotSet = 23,
+    InvalidPenaltySchedule = 24,
+    InvalidBasket = 25,
+    BasketNotSupported = 26,
+    AssetNotAllowed = 27,
+    AmountOutOfRange = 28,
+    AssetCapExceeded = 29,
+    InvalidWasmHash = 30,
+    InvalidVersion = 31,
+    TimeLockNotApproved = 32,
+    InvalidOperatorApproval = 33,
+    InvalidClawback = 34,
+    ClawbackActive = 35,
+    ClawbackUnavailable = 36,
+    InvalidReleaseSchedule = 37,
+    InvalidSplit = 38,
+    InvalidMerge = 39,
+    TemplateUnavailable = 40,
+    InvalidTemplate = 41,
+    DurationOutOfRange = 42,
+    TemplateRequired = 43,
+    PoolNotAllowed = 44,
+    AllocationNotReturned = 45,
+    AllocationsOutstanding = 46,
+    NftRenewFailed = 47,
+    RolloverWindowOpen = 48,
 }
 
 impl CommitmentError {
@@ -50,6 +90,37 @@ impl CommitmentError {
             CommitmentError::NotExpired => "Commitment has not expired yet",
             CommitmentError::ValueUpdateViolation => "Commitment has  value update voilation",
             CommitmentError::NotAuthorizedUpdater => "Commitment has not auth updater",
+            CommitmentError::OracleNotConfigured => "Price oracle not configured",
+            CommitmentError::StalePrice => "Oracle price is stale",
+            CommitmentError::PriceUnavailable => "Oracle price unavailable for asset",
+            CommitmentError::AlreadyMigrated => "Storage migration already completed",
+            CommitmentError::InvalidFeeBps => "Invalid fee: bps must be 0-10000",
+            CommitmentError::TreasuryNotSet => "Penalty treasury not set",
+            CommitmentError::InvalidPenaltySchedule => "Invalid early exit penalty schedule",
+            CommitmentError::InvalidBasket => "Invalid basket: check legs, weights and prices",
+            CommitmentError::BasketNotSupported => "Not supported for basket commitments",
+            CommitmentError::AssetNotAllowed => "Asset not allowed",
+            CommitmentError::AmountOutOfRange => "Amount outside the asset's size limits",
+            CommitmentError::AssetCapExceeded => "Asset cap exceeded",
+            CommitmentError::InvalidWasmHash => "Invalid WASM hash",
+            CommitmentError::InvalidVersion => "Invalid migration version",
+            CommitmentError::TimeLockNotApproved => "Action not approved by the time lock",
+            CommitmentError::InvalidOperatorApproval => "Invalid operator approval",
+            CommitmentError::InvalidClawback => "Invalid clawback deadline",
+            CommitmentError::ClawbackActive => "Funds are subject to clawback by the funder",
+            CommitmentError::ClawbackUnavailable => "Clawback not available",
+            CommitmentError::InvalidReleaseSchedule => "Invalid release schedule",
+            CommitmentError::InvalidSplit => "Invalid split amounts",
+            CommitmentError::InvalidMerge => "Commitments cannot be merged",
+            CommitmentError::TemplateUnavailable => "Commitment template not found or disabled",
+            CommitmentError::InvalidTemplate => "Invalid commitment template",
+            CommitmentError::DurationOutOfRange => "Duration outside the template's bounds",
+            CommitmentError::TemplateRequired => "Commitments must be created from a template",
+            CommitmentError::PoolNotAllowed => "Allocation pool not allowed by the template",
+            CommitmentError::AllocationNotReturned => "Allocation pool did not return funds",
+            CommitmentError::AllocationsOutstanding => "Commitment has funds allocated to pools",
+            CommitmentError::NftRenewFailed => "NFT renewal failed",
+            CommitmentError::RolloverWindowOpen => "Only the owner can settle a rollover yet",
         }
     }
 }
@@ -72,30 +143,401 @@ pub struct CommitmentCreatedEvent {
     pub timestamp: u64,
 }
 
+/// `CommitmentRules` as stored in storage version 5 (no release schedule).
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub struct CommitmentRules {
+pub struct CommitmentRulesV5 {
     pub duration_days: u32,
     pub max_loss_percent: u32,
-    pub commitment_type: String, // "safe", "balanced", "aggressive"
+    pub commitment_type: CommitmentType,
     pub early_exit_penalty: u32,
     pub min_fee_threshold: i128,
     pub grace_period_days: u32,
+    pub penalty_schedule: PenaltySchedule,
+    pub penalty_floor_percent: u32,
+    pub auto_rollover: bool,
 }
 
+/// `Commitment` as stored in storage version 5.
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub struct Commitment {
+pub struct CommitmentV5 {
     pub commitment_id: String,
     pub owner: Address,
     pub nft_token_id: u32,
-    pub rules: CommitmentRules,
+    pub rules: CommitmentRulesV5,
+    pub amount: i128,
+    pub asset_address: Address,
+    pub created_at: u64,
+    pub expires_at: u64,
+    pub current_value: i128,
+    pub status: CommitmentStatus,
+}
+
+impl CommitmentV5 {
+    /// Earlier commitments release everything at expiry.
+    fn upgrade(self) -> Commitment {
+        Commitment {
+            commitment_id: self.commitment_id,
+            owner: self.owner,
+            nft_token_id: self.nft_token_id,
+            rules: CommitmentRules {
+                duration_days: self.rules.duration_days,
+                max_loss_percent: self.rules.max_loss_percent,
+                commitment_type: self.rules.commitment_type,
+                early_exit_penalty: self.rules.early_exit_penalty,
+                min_fee_threshold: self.rules.min_fee_threshold,
+                grace_period_days: self.rules.grace_period_days,
+                penalty_schedule: self.rules.penalty_schedule,
+                penalty_floor_percent: self.rules.penalty_floor_percent,
+                auto_rollover: self.rules.auto_rollover,
+                release_schedule: ReleaseSchedule::AtExpiry,
+            },
+            amount: self.amount,
+            asset_address: self.asset_address,
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+            current_value: self.current_value,
+            status: self.status,
+        }
+    }
+}
+
+/// `CommitmentRules` as stored in storage version 4 (no auto-rollover flag).
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CommitmentRulesV4 {
+    pub duration_days: u32,
+    pub max_loss_percent: u32,
+    pub commitment_type: CommitmentType,
+    pub early_exit_penalty: u32,
+    pub min_fee_threshold: i128,
+    pub grace_period_days: u32,
+    pub penalty_schedule: PenaltySchedule,
+    pub penalty_floor_percent: u32,
+}
+
+/// `Commitment` as stored in storage version 4.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CommitmentV4 {
+    pub commitment_id: String,
+    pub owner: Address,
+    pub nft_token_id: u32,
+    pub rules: CommitmentRulesV4,
+    pub amount: i128,
+    pub asset_address: Address,
+    pub created_at: u64,
+    pub expires_at: u64,
+    pub current_value: i128,
+    pub status: CommitmentStatus,
+}
+
+impl CommitmentV4 {
+    /// Earlier commitments pay out at settlement.
+    fn upgrade(self) -> CommitmentV5 {
+        CommitmentV5 {
+            commitment_id: self.commitment_id,
+            owner: self.owner,
+            nft_token_id: self.nft_token_id,
+            rules: CommitmentRulesV5 {
+                duration_days: self.rules.duration_days,
+                max_loss_percent: self.rules.max_loss_percent,
+                commitment_type: self.rules.commitment_type,
+                early_exit_penalty: self.rules.early_exit_penalty,
+                min_fee_threshold: self.rules.min_fee_threshold,
+                grace_period_days: self.rules.grace_period_days,
+                penalty_schedule: self.rules.penalty_schedule,
+                penalty_floor_percent: self.rules.penalty_floor_percent,
+                auto_rollover: false,
+            },
+            amount: self.amount,
+            asset_address: self.asset_address,
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+            current_value: self.current_value,
+            status: self.status,
+        }
+    }
+}
+
+/// `CommitmentRules` as stored in storage versions 2-3 (no penalty schedule).
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CommitmentRulesV2 {
+    pub duration_days: u32,
+    pub max_loss_percent: u32,
+    pub commitment_type: CommitmentType,
+    pub early_exit_penalty: u32,
+    pub min_fee_threshold: i128,
+    pub grace_period_days: u32,
+}
+
+/// `Commitment` as stored in storage versions 2-3.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CommitmentV2 {
+    pub commitment_id: String,
+    pub owner: Address,
+    pub nft_token_id: u32,
+    pub rules: CommitmentRulesV2,
+    pub amount: i128,
+    pub asset_address: Address,
+    pub created_at: u64,
+    pub expires_at: u64,
+    pub current_value: i128,
+    pub status: CommitmentStatus,
+}
+
+impl CommitmentV2 {
+    /// Earlier commitments keep their flat penalty.
+    fn upgrade(self) -> CommitmentV4 {
+        CommitmentV4 {
+            commitment_id: self.commitment_id,
+            owner: self.owner,
+            nft_token_id: self.nft_token_id,
+            rules: CommitmentRulesV4 {
+                duration_days: self.rules.duration_days,
+                max_loss_percent: self.rules.max_loss_percent,
+                commitment_type: self.rules.commitment_type,
+                early_exit_penalty: self.rules.early_exit_penalty,
+                min_fee_threshold: self.rules.min_fee_threshold,
+                grace_period_days: self.rules.grace_period_days,
+                penalty_schedule: PenaltySchedule::Flat,
+                penalty_floor_percent: 0,
+            },
+            amount: self.amount,
+            asset_address: self.asset_address,
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+            current_value: self.current_value,
+            status: self.status,
+        }
+    }
+}
+
+/// `CommitmentRules` as stored before storage version 2 (string commitment type).
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CommitmentRulesV1 {
+    pub duration_days: u32,
+    pub max_loss_percent: u32,
+    pub commitment_type: String,
+    pub early_exit_penalty: u32,
+    pub min_fee_threshold: i128,
+    pub grace_period_days: u32,
+}
+
+/// `Commitment` as stored before storage version 2 (string status).
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CommitmentV1 {
+    pub commitment_id: String,
+    pub owner: Address,
+    pub nft_token_id: u32,
+    pub rules: CommitmentRulesV1,
     pub amount: i128,
     pub asset_address: Address,
     pub created_at: u64,
     pub expires_at: u64,
     pub current_value: i128,
-    pub status: String, // "active", "settled", "violated", "early_exit"
+    pub status: String,
+}
+
+impl CommitmentV1 {
+    fn upgrade(self, e: &Env) -> CommitmentV2 {
+        let commitment_type = CommitmentType::from_name(e, &self.rules.commitment_type)
+            .unwrap_or_else(|| {
+                fail(
+                    e,
+                    CommitmentError::InvalidCommitmentType,
+                    "upgrade_commitment",
+                )
+            });
+        let status = CommitmentStatus::from_name(e, &self.status)
+            .unwrap_or_else(|| fail(e, CommitmentError::InvalidStatus, "upgrade_commitment"));
+        CommitmentV2 {
+            commitment_id: self.commitment_id,
+            owner: self.owner,
+            nft_token_id: self.nft_token_id,
+            rules: CommitmentRulesV2 {
+                duration_days: self.rules.duration_days,
+                max_loss_percent: self.rules.max_loss_percent,
+                commitment_type,
+                early_exit_penalty: self.rules.early_exit_penalty,
+                min_fee_threshold: self.rules.min_fee_threshold,
+                grace_period_days: self.rules.grace_period_days,
+            },
+            amount: self.amount,
+            asset_address: self.asset_address,
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+            current_value: self.current_value,
+            status,
+        }
+    }
+}
+
+/// Mirror of `attestation_engine::HealthMetrics`, decoded from cross-contract reads.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HealthMetrics {
+    pub commitment_id: String,
+    pub current_value: i128,
+    pub initial_value: i128,
+    pub drawdown_percent: i128,
+    pub fees_generated: i128,
+    pub volatility_exposure: i128,
+    pub last_attestation: u64,
+    pub compliance_score: u32, // 0-100
+}
+
+/// Mirror of `price_oracle::PriceData`, decoded from cross-contract reads.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceData {
+    pub price: i128,
+    pub updated_at: u64,
+    pub decimals: u32,
+}
+
+/// Oracle price at which a commitment's `current_value` counts at par.
+///
+/// `current_value` is a token-unit balance and never follows the oracle; `revalue`
+/// compares today's price with the mark to value it for the loss checks.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceMark {
+    pub price: i128,
+    pub decimals: u32,
+    pub marked_at: u64,
+}
+
+/// What happens at settlement when attested fees are below `min_fee_threshold`.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FeeShortfallAction {
+    /// Record the shortfall and settle normally.
+    Flag,
+    /// Record the shortfall and withhold the missing fees from the settlement payout.
+    Rebate,
+}
+
+/// Outcome of the latest fee threshold check for a commitment.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeeCheck {
+    pub fees_generated: i128,
+    pub min_fee_threshold: i128,
+    pub shortfall: i128, // 0 when the threshold is met
+    pub checked_at: u64,
+}
+
+/// Criteria for `list_commitments`; unset fields and empty lists match every commitment.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CommitmentFilter {
+    pub statuses: Vec<CommitmentStatus>,       // any of
+    pub commitment_types: Vec<CommitmentType>, // any of
+    pub asset: Option<Address>,
+    pub owner: Option<Address>,
+    pub expires_from: Option<u64>, // inclusive lower bound on expires_at
+    pub expires_to: Option<u64>,   // inclusive upper bound on expires_at
+}
+
+impl CommitmentFilter {
+    fn matches(&self, commitment: &Commitment) -> bool {
+        (self.statuses.is_empty() || self.statuses.contains(commitment.status))
+            && (self.commitment_types.is_empty()
+                || self
+                    .commitment_types
+                    .contains(commitment.rules.commitment_type))
+            && self
+                .asset
+                .as_ref()
+                .is_none_or(|a| commitment.asset_address == *a)
+            && self.owner.as_ref().is_none_or(|o| commitment.owner == *o)
+            && self.expires_from.is_none_or(|t| commitment.expires_at >= t)
+            && self.expires_to.is_none_or(|t| commitment.expires_at <= t)
+    }
+}
+
+/// One page of `list_commitments` results.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CommitmentPage {
+    pub commitments: Vec<Commitment>,
+    /// Cursor for the next call; `None` once every candidate has been scanned.
+    pub next_cursor: Option<u64>,
+}
+
+/// One asset locked by a basket commitment.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BasketLeg {
+    pub asset_address: Address,
+    pub amount: i128,    // units of `asset_address` locked
+    pub weight_bps: u32, // target share of the basket's value at creation
+}
+
+/// Admin-managed limits for committing an asset. Zero for `max_amount` or a cap means
+/// no limit.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AssetConfig {
+    pub enabled: bool,    // new deposits allowed
+    pub min_amount: i128, // smallest commitment (or basket leg)
+    pub max_amount: i128, // largest commitment (or basket leg)
+    pub global_cap: i128, // most units locked across all commitments
+    pub owner_cap: i128,  // most units locked by one owner
+}
+
+/// Where early-exit penalties are sent.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PenaltyDestination {
+    /// Held for the penalty treasury, which withdraws it with `claim_penalties`.
+    Treasury,
+    /// Burned through the asset's token contract.
+    Burn,
+    /// Shared pro-rata by value among open single-asset commitments of the same asset and
+    /// type, claimed with `claim_penalty_share`. Goes to the treasury if there are none.
+    Redistribute,
+}
+
+/// Per-asset accounting of early-exit penalties.
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PenaltyAccount {
+    pub collected: i128,        // all penalties charged in this asset
+    pub burned: i128,           // sent to Burn
+    pub redistributed: i128,    // credited to remaining commitments
+    pub treasury_balance: i128, // held for the treasury, not yet claimed
+}
+
+/// Redistribution pool for one asset and commitment type.
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PenaltyPool {
+    pub total_value: i128,      // sum of the shares' values
+    pub reward_per_value: i128, // cumulative penalty per unit of value, scaled by PENALTY_SCALE
+}
+
+/// A commitment's share of its redistribution pool.
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PenaltyShare {
+    pub value: i128,            // current_value while open, 0 once closed
+    pub reward_per_value: i128, // pool.reward_per_value when `accrued` was last updated
+    pub accrued: i128,          // unclaimed penalties credited to this commitment
+}
+
+/// Secondary indexes over commitment ids, maintained on create and status changes.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CommitmentIndex {
+    Status(CommitmentStatus),
+    Asset(Address), // open commitments only
 }
 
 #[contracttype]
@@ -103,13 +545,54 @@ pub struct Commitment {
 pub enum DataKey {
     Admin,
     NftContract,
-    AllocationContract,        // authorized allocation logic contract
-    Commitment(String),        // commitment_id -> Commitment
-    OwnerCommitments(Address), // owner -> Vec<commitment_id>
-    TotalCommitments,          // counter
-    ReentrancyGuard,           // reentrancy protection flag
-    TotalValueLocked,          // aggregate value locked across active commitments
-    AuthorizedUpdaters,        // whitelist of authorized updaters
+    AllocationContract,                // authorized allocation logic contract
+    Commitment(String),                // commitment_id -> Commitment
+    OwnerCommitments(Address),         // owner -> Vec<commitment_id>
+    TotalCommitments,                  // counter
+    ReentrancyGuard,                   // reentrancy protection flag
+    TotalValueLocked,                  // aggregate value locked across active commitments
+    AuthorizedUpdaters,                // whitelist of authorized updaters
+    GraceDeadline(String),             // commitment_id -> end of grace window while AtRisk
+    AttestationEngine,                 // attestation engine queried for fee metrics
+    FeeShortfallAction,                // consequence applied at settlement on fee shortfall
+    FeeCheck(String),                  // commitment_id -> latest FeeCheck
+    PriceOracle,                       // price oracle used by `revalue`
+    PriceMark(String),                 // commitment_id -> PriceMark its value counts at par at
+    MarkedValue(String),               // commitment_id -> last oracle valuation (loss checks only)
+    StorageVersion,                    // layout of stored commitment records (see STORAGE_VERSION)
+    StorageMigrationCursor,            // next commitment counter to migrate
+    IndexLen(CommitmentIndex),         // number of ids in a secondary index
+    IndexEntry(CommitmentIndex, u64),  // (index, position) -> commitment_id
+    IndexPos(CommitmentIndex, String), // (index, commitment_id) -> position
+    KeeperBountyBps,                   // share of a settlement paid to `batch_settle` keepers
+    PenaltyDestination,                // where early-exit penalties are sent
+    PenaltyTreasury,                   // recipient of treasury penalties
+    PenaltyAccount(Address),           // asset -> PenaltyAccount
+    PenaltyShare(String),              // commitment_id -> PenaltyShare
+    // (asset, commitment type) -> PenaltyPool
+    PenaltyPool(Address, CommitmentType),
+    BasketLegs(String), // commitment_id -> Vec<BasketLeg> (basket commitments)
+    AssetValueLocked(Address), // asset -> units locked in open commitments
+    // (owner, asset) -> units of the asset locked in the owner's open commitments
+    OwnerAssetLocked(Address, Address),
+    AssetConfig(Address),            // asset -> AssetConfig
+    ListedAssets,                    // Vec<Address> of assets with an AssetConfig
+    AllowlistEnforced,               // reject assets without an AssetConfig
+    Version,                         // contract version (see CURRENT_VERSION)
+    TimeLock,                        // time_lock contract that must approve upgrades
+    TimeLockActionUsed(u64),         // time_lock action ids already consumed
+    PenaltySharesClaimed(Address),   // asset -> redistributed penalties paid out
+    OwnerOperator(Address, Address), // (owner, operator) -> OperatorApproval for all commitments
+    // (commitment_id, owner, operator) -> OperatorApproval for one commitment
+    CommitmentOperator(String, Address, Address),
+    Sponsorship(String), // commitment_id -> Sponsorship (third-party funded commitments)
+    VestingClaimed(String), // commitment_id -> amount claimed so far under its release schedule
+    Template(Symbol),    // template_id -> CommitmentTemplate
+    ListedTemplates,     // Vec<Symbol> of template ids, in listing order
+    TemplatesEnforced,   // only create commitments from templates
+    CommitmentTemplate(String), // commitment_id -> template_id it was created from
+    Allocations(String), // commitment_id -> Map<pool, amount> allocated out of core
+    RecallRecipient(String), // commitment_id -> payee of allocations recalled after closing
 }
 
 // ─── Token helpers ────────────────────────────────────────────────────────────
@@ -144,7 +627,7 @@ fn call_nft_mint(
     commitment_id: &String,
     duration_days: u32,
     max_loss_percent: u32,
-    commitment_type: &String,
+    commitment_type: CommitmentType,
     initial_amount: i128,
     asset_address: &Address,
 ) -> u32 {
@@ -153,251 +636,3907 @@ fn call_nft_mint(
     args.push_back(commitment_id.clone().into_val(e));
     args.push_back(duration_days.into_val(e));
     args.push_back(max_loss_percent.into_val(e));
-    args.push_back(commitment_type.clone().into_val(e));
+    args.push_back(String::from_str(e, commitment_type.as_str()).into_val(e));
     args.push_back(initial_amount.into_val(e));
     args.push_back(asset_address.clone().into_val(e));
 
     e.invoke_contract::<u32>(nft_contract, &Symbol::new(e, "mint"), args)
 }
 
-// ─── Storage helpers ──────────────────────────────────────────────────────────
+/// Call the NFT contract to record a new `initial_amount` for an active token.
+fn call_nft_update_initial_amount(
+    e: &Env,
+    nft_contract: &Address,
+    nft_token_id: u32,
+    new_amount: i128,
+) {
+    let mut args = Vec::new(e);
+    args.push_back(e.current_contract_address().into_val(e));
+    args.push_back(nft_token_id.into_val(e));
+    args.push_back(new_amount.into_val(e));
+    e.invoke_contract::<()>(nft_contract, &Symbol::new(e, "update_initial_amount"), args);
+}
 
-fn read_commitment(e: &Env, commitment_id: &String) -> Option<Commitment> {
-    e.storage()
-        .instance()
-        .get::<_, Commitment>(&DataKey::Commitment(commitment_id.clone()))
+/// Call the NFT contract to set an active token's exact expiry.
+fn call_nft_set_expires_at(e: &Env, nft_contract: &Address, nft_token_id: u32, expires_at: u64) {
+    let mut args = Vec::new(e);
+    args.push_back(e.current_contract_address().into_val(e));
+    args.push_back(nft_token_id.into_val(e));
+    args.push_back(expires_at.into_val(e));
+    e.invoke_contract::<()>(nft_contract, &Symbol::new(e, "set_expires_at"), args);
 }
 
-fn set_commitment(e: &Env, commitment: &Commitment) {
-    e.storage().instance().set(
-        &DataKey::Commitment(commitment.commitment_id.clone()),
-        commitment,
-    );
+/// Call the NFT contract to re-stamp an active token for a renewed commitment term.
+fn call_nft_renew(
+    e: &Env,
+    nft_contract: &Address,
+    commitment: &Commitment,
+) -> Result<(), CommitmentError> {
+    let mut args = Vec::new(e);
+    args.push_back(e.current_contract_address().into_val(e));
+    args.push_back(commitment.nft_token_id.into_val(e));
+    args.push_back(commitment.rules.duration_days.into_val(e));
+    args.push_back(commitment.rules.max_loss_percent.into_val(e));
+    args.push_back(commitment.amount.into_val(e));
+    args.push_back(commitment.rules.early_exit_penalty.into_val(e));
+    match e.try_invoke_contract::<(), Error>(nft_contract, &Symbol::new(e, "renew"), args) {
+        Ok(Ok(())) => Ok(()),
+        _ => Err(CommitmentError::NftRenewFailed),
+    }
 }
 
-fn has_commitment(e: &Env, commitment_id: &String) -> bool {
-    e.storage()
-        .instance()
-        .has(&DataKey::Commitment(commitment_id.clone()))
+/// Call the NFT contract to record the legs of a basket commitment on its token.
+fn call_nft_set_basket(e: &Env, nft_contract: &Address, nft_token_id: u32, legs: &Vec<BasketLeg>) {
+    let mut args = Vec::new(e);
+    args.push_back(e.current_contract_address().into_val(e));
+    args.push_back(nft_token_id.into_val(e));
+    args.push_back(legs.clone().into_val(e));
+    e.invoke_contract::<()>(nft_contract, &Symbol::new(e, "set_basket"), args);
 }
 
-fn require_no_reentrancy(e: &Env) {
-    let guard: bool = e
+/// Read attested fees for a commitment from the attestation engine.
+/// Commitments without stored metrics have generated no fees yet.
+fn read_attested_fees(e: &Env, attestation_engine: &Address, commitment_id: &String) -> i128 {
+    let mut args = Vec::new(e);
+    args.push_back(commitment_id.clone().into_val(e));
+    let metrics = e.invoke_contract::<Option<HealthMetrics>>(
+        attestation_engine,
+        &Symbol::new(e, "get_stored_health_metrics"),
+        args,
+    );
+    metrics.map(|m| m.fees_generated).unwrap_or(0)
+}
+
+/// Compare attested fees with `min_fee_threshold` and record the result.
+/// Returns `None` when no threshold applies or no attestation engine is configured.
+fn run_fee_check(e: &Env, commitment: &Commitment) -> Option<FeeCheck> {
+    if commitment.rules.min_fee_threshold <= 0 {
+        return None;
+    }
+    let attestation_engine = e
         .storage()
         .instance()
-        .get::<_, bool>(&DataKey::ReentrancyGuard)
-        .unwrap_or(false);
+        .get::<_, Address>(&DataKey::AttestationEngine)?;
+
+    let fees_generated = read_attested_fees(e, &attestation_engine, &commitment.commitment_id);
+    let shortfall = if fees_generated < commitment.rules.min_fee_threshold {
+        commitment.rules.min_fee_threshold - fees_generated
+    } else {
+        0
+    };
+    let check = FeeCheck {
+        fees_generated,
+        min_fee_threshold: commitment.rules.min_fee_threshold,
+        shortfall,
+        checked_at: e.ledger().timestamp(),
+    };
+    write_persistent(
+        e,
+        &DataKey::FeeCheck(commitment.commitment_id.clone()),
+        &check,
+    );
 
-    if guard {
-        fail(
-            e,
-            CommitmentError::ReentrancyDetected,
-            "require_no_reentrancy",
+    if shortfall > 0 {
+        e.events().publish(
+            (symbol_short!("FeeShort"), commitment.commitment_id.clone()),
+            (
+                fees_generated,
+                commitment.rules.min_fee_threshold,
+                shortfall,
+            ),
         );
     }
+    Some(check)
 }
 
-fn set_reentrancy_guard(e: &Env, value: bool) {
-    e.storage()
-        .instance()
-        .set(&DataKey::ReentrancyGuard, &value);
-}
+/// `price_oracle::OracleError::StalePrice`
+const ORACLE_STALE_PRICE: u32 = 6;
 
-/// Require that the caller is the admin stored in this contract.
-fn require_admin(e: &Env, caller: &Address) {
-    caller.require_auth();
-    let admin = e
-        .storage()
-        .instance()
-        .get::<_, Address>(&DataKey::Admin)
-        .unwrap_or_else(|| fail(e, CommitmentError::NotInitialized, "require_admin"));
-    if *caller != admin {
-        fail(e, CommitmentError::Unauthorized, "require_admin");
+/// Read a fresh price for `asset` via `price_oracle::get_price_valid`.
+/// Stale prices map to `StalePrice`; any other oracle failure to `PriceUnavailable`.
+fn read_oracle_price(
+    e: &Env,
+    price_oracle: &Address,
+    asset: &Address,
+) -> Result<PriceData, CommitmentError> {
+    let mut args = Vec::new(e);
+    args.push_back(asset.clone().into_val(e));
+    args.push_back(Option::<u64>::None.into_val(e));
+    match e.try_invoke_contract::<PriceData, Error>(
+        price_oracle,
+        &Symbol::new(e, "get_price_valid"),
+        args,
+    ) {
+        Ok(Ok(data)) if data.price > 0 => Ok(data),
+        Err(Ok(err)) if err == Error::from_contract_error(ORACLE_STALE_PRICE) => {
+            Err(CommitmentError::StalePrice)
+        }
+        _ => Err(CommitmentError::PriceUnavailable),
     }
 }
 
-fn require_authorized_updater(e: &Env, caller: &Address) {
-    caller.require_auth();
-    let updaters: Vec<Address> = e
-        .storage()
-        .instance()
-        .get::<_, Vec<Address>>(&DataKey::AuthorizedUpdaters)
-        .unwrap_or(Vec::new(e));
-    if !updaters.contains(caller) {
-        fail(e, CommitmentError::NotAuthorizedUpdater, "Unauthorized");
+/// Scale `value` by the price move from `mark` to `price`, normalising decimals.
+fn mark_to_market(value: i128, mark: &PriceMark, price: &PriceData) -> i128 {
+    let (numerator, denominator) = if price.decimals >= mark.decimals {
+        let scale = 10i128.pow(price.decimals - mark.decimals);
+        (price.price, SafeMath::mul(mark.price, scale))
+    } else {
+        let scale = 10i128.pow(mark.decimals - price.decimals);
+        (SafeMath::mul(price.price, scale), mark.price)
+    };
+    SafeMath::div(SafeMath::mul(value, numerator), denominator)
+}
+
+fn set_price_mark(e: &Env, commitment_id: &String, price: &PriceData) {
+    let mark = PriceMark {
+        price: price.price,
+        decimals: price.decimals,
+        marked_at: e.ledger().timestamp(),
+    };
+    write_persistent(e, &DataKey::PriceMark(commitment_id.clone()), &mark);
+}
+
+fn read_price_mark(e: &Env, commitment_id: &String) -> Option<PriceMark> {
+    read_persistent::<PriceMark>(e, &DataKey::PriceMark(commitment_id.clone()))
+}
+
+/// Value `held` token units at `price`; without a mark they count at par.
+fn marked_value(held: i128, mark: Option<&PriceMark>, price: &PriceData) -> i128 {
+    match mark {
+        Some(mark) => mark_to_market(held, mark, price),
+        None => held,
     }
 }
 
-fn add_authorized_updater(e: &Env, updater: &Address) {
-    let mut updaters: Vec<Address> = e
+/// Value `held` units of a marked single-asset commitment at today's oracle price, if a
+/// usable price is available.
+fn oracle_valuation(e: &Env, commitment: &Commitment, held: i128) -> Option<i128> {
+    if read_basket_legs(e, &commitment.commitment_id).is_some() {
+        return None;
+    }
+    let mark = read_price_mark(e, &commitment.commitment_id)?;
+    let price_oracle = e
         .storage()
         .instance()
-        .get::<_, Vec<Address>>(&DataKey::AuthorizedUpdaters)
-        .unwrap_or(Vec::new(e));
-    if !updaters.contains(updater) {
-        updaters.push_back(updater.clone());
-        e.storage()
-            .instance()
-            .set(&DataKey::AuthorizedUpdaters, &updaters);
-    }
+        .get::<_, Address>(&DataKey::PriceOracle)?;
+    let price = read_oracle_price(e, &price_oracle, &commitment.asset_address).ok()?;
+    Some(mark_to_market(held, &mark, &price))
 }
 
-fn remove_authorized_updater(e: &Env, updater: &Address) {
-    let mut updaters: Vec<Address> = e
+/// Price to re-anchor a commitment's mark at when value joins it: `None` when nothing
+/// being combined carries a mark or no oracle is configured.
+fn remark_price(
+    e: &Env,
+    asset: &Address,
+    marked: bool,
+) -> Result<Option<PriceData>, CommitmentError> {
+    if !marked {
+        return Ok(None);
+    }
+    match e
         .storage()
         .instance()
-        .get::<_, Vec<Address>>(&DataKey::AuthorizedUpdaters)
-        .unwrap_or(Vec::new(e));
-    if let Some(idx) = updaters.iter().position(|a| a == *updater) {
-        updaters.remove(idx as u32);
-        e.storage()
-            .instance()
-            .set(&DataKey::AuthorizedUpdaters, &updaters);
+        .get::<_, Address>(&DataKey::PriceOracle)
+    {
+        Some(price_oracle) => read_oracle_price(e, &price_oracle, asset).map(Some),
+        None => Ok(None),
     }
 }
 
-// ─── Pause helpers (free functions used by the contract impl) ─────────────────
-
-/// Pause the contract. Caller must be admin.
-pub fn pause(e: Env, caller: Address) {
-    require_admin(&e, &caller);
-    Pausable::pause(&e);
+/// Re-anchor a commitment's mark after value joined it, so `held` token units are worth
+/// `marked` at `price`: value that joined counts at par today, while value already held
+/// keeps its gain or loss.
+fn blend_price_mark(e: &Env, commitment_id: &String, held: i128, marked: i128, price: &PriceData) {
+    let blended = if marked > 0 {
+        SafeMath::div(SafeMath::mul(held, price.price), marked).max(1)
+    } else {
+        price.price
+    };
+    let mark = PriceMark {
+        price: blended,
+        decimals: price.decimals,
+        marked_at: e.ledger().timestamp(),
+    };
+    write_persistent(e, &DataKey::PriceMark(commitment_id.clone()), &mark);
+    write_marked_value(e, commitment_id, marked);
 }
 
-/// Unpause the contract. Caller must be admin.
-pub fn unpause(e: Env, caller: Address) {
-    require_admin(&e, &caller);
-    Pausable::unpause(&e);
+/// Last oracle valuation of a commitment, or its `current_value` if it was never valued.
+fn read_marked_value(e: &Env, commitment: &Commitment) -> i128 {
+    read_persistent::<i128>(e, &DataKey::MarkedValue(commitment.commitment_id.clone()))
+        .unwrap_or(commitment.current_value)
 }
 
-/// Returns `true` if the contract is currently paused.
-pub fn is_paused(e: Env) -> bool {
-    Pausable::is_paused(&e)
+fn write_marked_value(e: &Env, commitment_id: &String, value: i128) {
+    write_persistent(e, &DataKey::MarkedValue(commitment_id.clone()), &value);
 }
 
-#[contract]
-pub struct CommitmentCoreContract;
-
+// ─── Penalty helpers ─────────────────────────────────────────────────────────
+
+/// Check a penalty schedule: percents within 0-100, steps non-empty and strictly ascending,
+/// and a floor no higher than the flat penalty it would otherwise override.
+fn validate_penalty_schedule(e: &Env, rules: &CommitmentRules) {
+    Validation::require_valid_percent(rules.early_exit_penalty);
+    Validation::require_valid_percent(rules.penalty_floor_percent);
+    let valid = match &rules.penalty_schedule {
+        PenaltySchedule::Flat => rules.penalty_floor_percent <= rules.early_exit_penalty,
+        PenaltySchedule::Linear => rules.penalty_floor_percent <= rules.early_exit_penalty,
+        PenaltySchedule::Steps(steps) => {
+            let mut previous = 0;
+            !steps.is_empty()
+                && steps.iter().all(|step| {
+                    let ascending = step.until_elapsed_percent > previous
+                        && step.until_elapsed_percent <= 100
+                        && step.penalty_percent <= 100;
+                    previous = step.until_elapsed_percent;
+                    ascending
+                })
+        }
+    };
+    if !valid {
+        fail(
+            e,
+            CommitmentError::InvalidPenaltySchedule,
+            "validate_penalty_schedule",
+        );
+    }
+}
+
+/// Early-exit penalty on `amount` withdrawn from `commitment` now, following its
+/// penalty schedule and floor.
+fn early_exit_penalty_amount(e: &Env, commitment: &Commitment, amount: i128) -> i128 {
+    let rules = &commitment.rules;
+    let term = commitment.expires_at.saturating_sub(commitment.created_at);
+    let elapsed = e
+        .ledger()
+        .timestamp()
+        .saturating_sub(commitment.created_at)
+        .min(term);
+
+    let scheduled = match &rules.penalty_schedule {
+        PenaltySchedule::Flat => SafeMath::penalty_amount(amount, rules.early_exit_penalty),
+        PenaltySchedule::Linear if term == 0 => 0,
+        PenaltySchedule::Linear => {
+            let full = SafeMath::penalty_amount(amount, rules.early_exit_penalty);
+            SafeMath::div(SafeMath::mul(full, (term - elapsed) as i128), term as i128)
+        }
+        PenaltySchedule::Steps(steps) => {
+            // Compare elapsed/term with each step without rounding
+            let percent = steps
+                .iter()
+                .find(|step| {
+                    (elapsed as u128) * 100 < (term as u128) * step.until_elapsed_percent as u128
+                })
+                .map_or(0, |step| step.penalty_percent);
+            SafeMath::penalty_amount(amount, percent)
+        }
+    };
+    scheduled.max(SafeMath::penalty_amount(
+        amount,
+        rules.penalty_floor_percent,
+    ))
+}
+
+/// Fixed-point scale of `PenaltyPool::reward_per_value`.
+const PENALTY_SCALE: i128 = 1_000_000_000_000;
+
+fn read_penalty_destination(e: &Env) -> PenaltyDestination {
+    e.storage()
+        .instance()
+        .get::<_, PenaltyDestination>(&DataKey::PenaltyDestination)
+        .unwrap_or(PenaltyDestination::Treasury)
+}
+
+fn read_penalty_account(e: &Env, asset: &Address) -> PenaltyAccount {
+    e.storage()
+        .instance()
+        .get::<_, PenaltyAccount>(&DataKey::PenaltyAccount(asset.clone()))
+        .unwrap_or_default()
+}
+
+fn write_penalty_account(e: &Env, asset: &Address, account: &PenaltyAccount) {
+    e.storage()
+        .instance()
+        .set(&DataKey::PenaltyAccount(asset.clone()), account);
+}
+
+fn read_penalty_pool(e: &Env, asset: &Address, commitment_type: CommitmentType) -> PenaltyPool {
+    e.storage()
+        .instance()
+        .get::<_, PenaltyPool>(&DataKey::PenaltyPool(asset.clone(), commitment_type))
+        .unwrap_or_default()
+}
+
+/// Penalties owed to a share, including those distributed since it was last updated.
+fn pending_penalty_share(share: &PenaltyShare, pool: &PenaltyPool) -> i128 {
+    let delta = SafeMath::sub(pool.reward_per_value, share.reward_per_value);
+    SafeMath::add(
+        share.accrued,
+        SafeMath::mul(share.value, delta) / PENALTY_SCALE,
+    )
+}
+
+/// Settle a commitment's accrued penalty share and re-weight it by its current value
+/// (0 once it is no longer open). Runs on every commitment write, so commitments created
+/// before redistribution was available join their pool the next time they change.
+///
+/// Baskets never hold a share: their value is quoted in an asset they did not deposit,
+/// and their leg penalties are already shared out in each leg's own asset.
+fn sync_penalty_share(e: &Env, commitment: &Commitment) {
+    let value = if commitment.status.is_open()
+        && read_basket_legs(e, &commitment.commitment_id).is_none()
+    {
+        commitment.current_value.max(0)
+    } else {
+        0
+    };
+    let share_key = DataKey::PenaltyShare(commitment.commitment_id.clone());
+    let share = read_persistent::<PenaltyShare>(e, &share_key);
+    if share.is_none() && value == 0 {
+        return;
+    }
+    let share = share.unwrap_or_default();
+
+    let pool_key = DataKey::PenaltyPool(
+        commitment.asset_address.clone(),
+        commitment.rules.commitment_type,
+    );
+    let mut pool = read_penalty_pool(
+        e,
+        &commitment.asset_address,
+        commitment.rules.commitment_type,
+    );
+    let accrued = pending_penalty_share(&share, &pool);
+    if share.value != value {
+        pool.total_value = SafeMath::add(SafeMath::sub(pool.total_value, share.value), value);
+        e.storage().instance().set(&pool_key, &pool);
+    }
+    write_persistent(
+        e,
+        &share_key,
+        &PenaltyShare {
+            value,
+            reward_per_value: pool.reward_per_value,
+            accrued,
+        },
+    );
+}
+
+/// Account for an early-exit penalty charged in `asset` according to `PenaltyDestination`.
+/// Returns the amount to burn, which the caller does in its interactions phase.
+/// Call after writing the commitment, so a full exit no longer shares in its own penalty.
+fn route_penalty(e: &Env, commitment: &Commitment, asset: &Address, penalty: i128) -> i128 {
+    if penalty <= 0 {
+        return 0;
+    }
+    let mut account = read_penalty_account(e, asset);
+    account.collected = SafeMath::add(account.collected, penalty);
+
+    let mut destination = read_penalty_destination(e);
+    if destination == PenaltyDestination::Redistribute {
+        let pool_key = DataKey::PenaltyPool(asset.clone(), commitment.rules.commitment_type);
+        let mut pool = read_penalty_pool(e, asset, commitment.rules.commitment_type);
+        if pool.total_value > 0 {
+            let increment = SafeMath::mul(penalty, PENALTY_SCALE) / pool.total_value;
+            pool.reward_per_value = SafeMath::add(pool.reward_per_value, increment);
+            e.storage().instance().set(&pool_key, &pool);
+            account.redistributed = SafeMath::add(account.redistributed, penalty);
+        } else {
+            // Nobody left to share with
+            destination = PenaltyDestination::Treasury;
+        }
+    }
+
+    let mut burn = 0;
+    match destination {
+        PenaltyDestination::Treasury => {
+            account.treasury_balance = SafeMath::add(account.treasury_balance, penalty);
+        }
+        PenaltyDestination::Burn => {
+            account.burned = SafeMath::add(account.burned, penalty);
+            burn = penalty;
+        }
+        PenaltyDestination::Redistribute => {}
+    }
+    write_penalty_account(e, asset, &account);
+
+    e.events().publish(
+        (symbol_short!("Penalty"), commitment.commitment_id.clone()),
+        (destination, penalty, e.ledger().timestamp()),
+    );
+    burn
+}
+
+// ─── Basket helpers ───────────────────────────────────────────────────────────
+
+/// Most legs a basket commitment may hold.
+pub const MAX_BASKET_LEGS: u32 = 8;
+/// How far a leg's share of the basket value may be from its target weight at creation.
+pub const BASKET_WEIGHT_TOLERANCE_BPS: u32 = 100;
+
+fn read_basket_legs(e: &Env, commitment_id: &String) -> Option<Vec<BasketLeg>> {
+    read_persistent::<Vec<BasketLeg>>(e, &DataKey::BasketLegs(commitment_id.clone()))
+}
+
+/// Convert `amount` of an asset priced at `price` into units of the quote asset.
+fn quote_value(amount: i128, price: &PriceData, quote: &PriceData) -> i128 {
+    let (numerator, denominator) = if quote.decimals >= price.decimals {
+        let scale = 10i128.pow(quote.decimals - price.decimals);
+        (SafeMath::mul(price.price, scale), quote.price)
+    } else {
+        let scale = 10i128.pow(price.decimals - quote.decimals);
+        (price.price, SafeMath::mul(quote.price, scale))
+    };
+    SafeMath::div(SafeMath::mul(amount, numerator), denominator)
+}
+
+/// Value each leg in the quote asset through the price oracle.
+fn basket_leg_values(
+    e: &Env,
+    price_oracle: &Address,
+    quote: &PriceData,
+    legs: &Vec<BasketLeg>,
+) -> Result<Vec<i128>, CommitmentError> {
+    let mut values = Vec::new(e);
+    for leg in legs.iter() {
+        let price = read_oracle_price(e, price_oracle, &leg.asset_address)?;
+        values.push_back(quote_value(leg.amount, &price, quote));
+    }
+    Ok(values)
+}
+
+/// Check basket legs: 2 to `MAX_BASKET_LEGS` distinct assets with positive amounts and
+/// weights summing to 100%.
+fn validate_basket_legs(legs: &Vec<BasketLeg>) -> Result<(), CommitmentError> {
+    if legs.len() < 2 || legs.len() > MAX_BASKET_LEGS {
+        return Err(CommitmentError::InvalidBasket);
+    }
+    let mut total_weight: u32 = 0;
+    for (i, leg) in legs.iter().enumerate() {
+        if leg.amount <= 0 || leg.weight_bps == 0 {
+            return Err(CommitmentError::InvalidBasket);
+        }
+        if legs
+            .iter()
+            .skip(i + 1)
+            .any(|other| other.asset_address == leg.asset_address)
+        {
+            return Err(CommitmentError::InvalidBasket);
+        }
+        total_weight = total_weight.saturating_add(leg.weight_bps);
+    }
+    if total_weight != BPS_MAX {
+        return Err(CommitmentError::InvalidBasket);
+    }
+    Ok(())
+}
+
+/// Check that each leg's share of `total` is within `BASKET_WEIGHT_TOLERANCE_BPS` of its
+/// target weight.
+fn validate_basket_weights(
+    legs: &Vec<BasketLeg>,
+    values: &Vec<i128>,
+    total: i128,
+) -> Result<(), CommitmentError> {
+    if total <= 0 {
+        return Err(CommitmentError::InvalidBasket);
+    }
+    for (leg, value) in legs.iter().zip(values.iter()) {
+        let share_bps = SafeMath::div(SafeMath::mul(value, BPS_MAX as i128), total);
+        if (share_bps - leg.weight_bps as i128).abs() > BASKET_WEIGHT_TOLERANCE_BPS as i128 {
+            return Err(CommitmentError::InvalidBasket);
+        }
+    }
+    Ok(())
+}
+
+/// Units of `asset` locked in open commitments.
+fn read_asset_value_locked(e: &Env, asset: &Address) -> i128 {
+    e.storage()
+        .instance()
+        .get::<_, i128>(&DataKey::AssetValueLocked(asset.clone()))
+        .unwrap_or(0)
+}
+
+/// Adjust the value locked for `asset`, in total and for `owner`. Commitments opened
+/// before per-asset tracking are not included, so both are floored at zero when they
+/// close.
+fn adjust_asset_value_locked(e: &Env, owner: &Address, asset: &Address, delta: i128) {
+    adjust_asset_total_locked(e, asset, delta);
+    adjust_owner_asset_locked(e, owner, asset, delta);
+}
+
+fn adjust_asset_total_locked(e: &Env, asset: &Address, delta: i128) {
+    let value = SafeMath::add(read_asset_value_locked(e, asset), delta).max(0);
+    e.storage()
+        .instance()
+        .set(&DataKey::AssetValueLocked(asset.clone()), &value);
+}
+
+fn adjust_owner_asset_locked(e: &Env, owner: &Address, asset: &Address, delta: i128) {
+    let value = SafeMath::add(owner_asset_locked(e, owner, asset), delta).max(0);
+    write_persistent(
+        e,
+        &DataKey::OwnerAssetLocked(owner.clone(), asset.clone()),
+        &value,
+    );
+}
+
+/// Share of `leg_amount` matching `part` out of `whole`; the whole leg when `whole` is
+/// not positive.
+fn pro_rata(leg_amount: i128, part: i128, whole: i128) -> i128 {
+    if whole <= 0 {
+        return leg_amount;
+    }
+    SafeMath::div(SafeMath::mul(leg_amount, part), whole)
+}
+
+// ─── Asset registry helpers ───────────────────────────────────────────────────
+
+fn read_asset_config(e: &Env, asset: &Address) -> Option<AssetConfig> {
+    e.storage()
+        .instance()
+        .get::<_, AssetConfig>(&DataKey::AssetConfig(asset.clone()))
+}
+
+fn read_listed_assets(e: &Env) -> Vec<Address> {
+    e.storage()
+        .instance()
+        .get::<_, Vec<Address>>(&DataKey::ListedAssets)
+        .unwrap_or_else(|| Vec::new(e))
+}
+
+fn allowlist_enforced(e: &Env) -> bool {
+    e.storage()
+        .instance()
+        .get::<_, bool>(&DataKey::AllowlistEnforced)
+        .unwrap_or(false)
+}
+
+/// Units of `asset` locked in `owner`'s open commitments, basket legs included.
+fn owner_asset_locked(e: &Env, owner: &Address, asset: &Address) -> i128 {
+    read_persistent::<i128>(e, &DataKey::OwnerAssetLocked(owner.clone(), asset.clone()))
+        .unwrap_or(0)
+}
+
+/// Check a deposit of `amount` into a position of `size` against the asset's config.
+/// Assets without a config are accepted unless the allowlist is enforced.
+fn check_asset_limits(
+    e: &Env,
+    owner: &Address,
+    asset: &Address,
+    amount: i128,
+    size: i128,
+) -> Result<(), CommitmentError> {
+    let config = match read_asset_config(e, asset) {
+        Some(config) => config,
+        None if allowlist_enforced(e) => return Err(CommitmentError::AssetNotAllowed),
+        None => return Ok(()),
+    };
+    if !config.enabled {
+        return Err(CommitmentError::AssetNotAllowed);
+    }
+    if size < config.min_amount || (config.max_amount > 0 && size > config.max_amount) {
+        return Err(CommitmentError::AmountOutOfRange);
+    }
+    if config.global_cap > 0
+        && SafeMath::add(read_asset_value_locked(e, asset), amount) > config.global_cap
+    {
+        return Err(CommitmentError::AssetCapExceeded);
+    }
+    if config.owner_cap > 0
+        && SafeMath::add(owner_asset_locked(e, owner, asset), amount) > config.owner_cap
+    {
+        return Err(CommitmentError::AssetCapExceeded);
+    }
+    Ok(())
+}
+
+// ─── Allocation helpers ───────────────────────────────────────────────────────
+
+// Funds a commitment allocates to pools stay part of its `current_value`; the ledger
+// records which pool holds how much until it is recalled.
+
+fn read_allocations(e: &Env, commitment_id: &String) -> Map<Address, i128> {
+    read_persistent(e, &DataKey::Allocations(commitment_id.clone())).unwrap_or_else(|| Map::new(e))
+}
+
+fn write_allocations(e: &Env, commitment_id: &String, allocations: &Map<Address, i128>) {
+    let key = DataKey::Allocations(commitment_id.clone());
+    if allocations.is_empty() {
+        e.storage().persistent().remove(&key);
+    } else {
+        write_persistent(e, &key, allocations);
+    }
+}
+
+fn allocated_total(allocations: &Map<Address, i128>) -> i128 {
+    allocations.values().iter().fold(0i128, SafeMath::add)
+}
+
+/// Pull `amount` of the commitment's asset back from `pool` through the pool interface,
+/// checking that the funds actually arrived.
+fn recall_allocation(
+    e: &Env,
+    commitment: &Commitment,
+    pool: &Address,
+    amount: i128,
+) -> Result<(), CommitmentError> {
+    let contract_address = e.current_contract_address();
+    let token_client = token::Client::new(e, &commitment.asset_address);
+    let balance_before = token_client.balance(&contract_address);
+    let result = AllocationPoolClient::new(e, pool).try_withdraw(
+        &commitment.commitment_id,
+        &commitment.asset_address,
+        &amount,
+        &contract_address,
+    );
+    let returned = SafeMath::sub(token_client.balance(&contract_address), balance_before);
+    if !matches!(result, Ok(Ok(()))) || returned < amount {
+        return Err(CommitmentError::AllocationNotReturned);
+    }
+    e.events().publish(
+        (
+            symbol_short!("Dealloc"),
+            commitment.commitment_id.clone(),
+            pool.clone(),
+        ),
+        (amount, e.ledger().timestamp()),
+    );
+    Ok(())
+}
+
+/// Recall everything the commitment has allocated, pool by pool, before it pays out.
+/// Each allocation leaves the ledger once its funds are back, so a failing pool leaves
+/// only its own entry behind. Returns the amount still outstanding.
+fn unwind_allocations(e: &Env, commitment: &Commitment) -> i128 {
+    let mut allocations = read_allocations(e, &commitment.commitment_id);
+    if allocations.is_empty() {
+        return 0;
+    }
+    for (pool, amount) in allocations.clone().iter() {
+        if recall_allocation(e, commitment, &pool, amount).is_ok() {
+            allocations.remove(pool);
+        }
+    }
+    write_allocations(e, &commitment.commitment_id, &allocations);
+    allocated_total(&allocations)
+}
+
+/// Make sure `amount` of the commitment's value is held in the contract, recalling its
+/// allocations if not. For payouts that leave the commitment open.
+fn require_unallocated(e: &Env, commitment: &Commitment, amount: i128, context: &str) {
+    let allocated = allocated_total(&read_allocations(e, &commitment.commitment_id));
+    if SafeMath::sub(commitment.current_value, allocated) < amount
+        && unwind_allocations(e, commitment) > 0
+    {
+        set_reentrancy_guard(e, false);
+        fail(e, CommitmentError::AllocationNotReturned, context);
+    }
+}
+
+// ─── Template helpers ─────────────────────────────────────────────────────────
+
+/// Admin-defined commitment offering, used by `create_commitment_from_template`.
+///
+/// `rules` are copied to every commitment made from the template, except `duration_days`,
+/// which each commitment picks within `min_duration_days..=max_duration_days`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CommitmentTemplate {
+    pub enabled: bool, // new commitments allowed
+    pub asset_address: Address,
+    pub min_duration_days: u32,
+    pub max_duration_days: u32,
+    pub rules: CommitmentRules,
+    pub allocation_pool: Option<Address>, // only pool `allocate` may send its funds to
+}
+
+fn read_template(e: &Env, template_id: &Symbol) -> Option<CommitmentTemplate> {
+    e.storage()
+        .instance()
+        .get::<_, CommitmentTemplate>(&DataKey::Template(template_id.clone()))
+}
+
+fn read_listed_templates(e: &Env) -> Vec<Symbol> {
+    e.storage()
+        .instance()
+        .get::<_, Vec<Symbol>>(&DataKey::ListedTemplates)
+        .unwrap_or_else(|| Vec::new(e))
+}
+
+fn templates_enforced(e: &Env) -> bool {
+    e.storage()
+        .instance()
+        .get::<_, bool>(&DataKey::TemplatesEnforced)
+        .unwrap_or(false)
+}
+
+fn read_commitment_template(e: &Env, commitment_id: &String) -> Option<Symbol> {
+    read_persistent(e, &DataKey::CommitmentTemplate(commitment_id.clone()))
+}
+
+/// Reject free-form creation while templates are enforced.
+fn require_templates_not_enforced(e: &Env, context: &str) {
+    if templates_enforced(e) {
+        fail(e, CommitmentError::TemplateRequired, context);
+    }
+}
+
+// ─── Sponsorship helpers ──────────────────────────────────────────────────────
+
+/// Funding of a commitment created with `create_commitment_for`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Sponsorship {
+    pub funder: Address,
+    pub clawback_until: Option<u64>, // funder may claw the position back before this time
+}
+
+fn read_sponsorship(e: &Env, commitment_id: &String) -> Option<Sponsorship> {
+    read_persistent(e, &DataKey::Sponsorship(commitment_id.clone()))
+}
+
+/// The commitment's funder, while the funder can still claw it back.
+fn active_clawback_funder(e: &Env, commitment_id: &String) -> Option<Address> {
+    read_sponsorship(e, commitment_id).and_then(|sponsorship| match sponsorship.clawback_until {
+        Some(deadline) if e.ledger().timestamp() < deadline => Some(sponsorship.funder),
+        _ => None,
+    })
+}
+
+/// Close an open commitment without penalty and return what it holds as
+/// `(asset, amount)` payouts: `current_value`, or every basket leg in full. The caller
+/// makes the transfers.
+///
+/// `retained` is the part of a single-asset commitment's value still out in allocation
+/// pools. It is left out of the payout and stays in the asset's total and in TVL until
+/// `deallocate` recalls it.
+fn release_commitment(
+    e: &Env,
+    commitment: &mut Commitment,
+    retained: i128,
+) -> Vec<(Address, i128)> {
+    let exit_value = commitment.current_value;
+    let released = SafeMath::sub(exit_value, retained);
+    set_status(e, commitment, CommitmentStatus::EarlyExit);
+    commitment.current_value = 0;
+    set_commitment(e, commitment);
+    remove_grace_deadline(e, &commitment.commitment_id);
+
+    let mut payouts = Vec::new(e);
+    match read_basket_legs(e, &commitment.commitment_id) {
+        Some(legs) => {
+            for leg in legs.iter() {
+                adjust_asset_value_locked(e, &commitment.owner, &leg.asset_address, -leg.amount);
+                payouts.push_back((leg.asset_address.clone(), leg.amount));
+            }
+        }
+        None => {
+            adjust_asset_total_locked(e, &commitment.asset_address, -released);
+            adjust_owner_asset_locked(e, &commitment.owner, &commitment.asset_address, -exit_value);
+            payouts.push_back((commitment.asset_address.clone(), released));
+        }
+    }
+
+    let current_tvl = e
+        .storage()
+        .instance()
+        .get::<_, i128>(&DataKey::TotalValueLocked)
+        .unwrap_or(0);
+    e.storage()
+        .instance()
+        .set(&DataKey::TotalValueLocked, &(current_tvl - released));
+    payouts
+}
+
+// ─── Vesting helpers ──────────────────────────────────────────────────────────
+
+/// Progress of a commitment through its release schedule.
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct VestingStatus {
+    pub claimed_amount: i128, // paid out by `claim_vested`
+    pub vested_amount: i128,  // released so far, claimed or not
+    pub releasable: i128,     // vested but not yet claimed
+}
+
+/// Check a release schedule: a cliff within the term, a non-zero tranche interval no
+/// longer than the term, and no gradual release on rolling-over commitments.
+fn validate_release_schedule(e: &Env, rules: &CommitmentRules) {
+    let valid = match rules.release_schedule {
+        ReleaseSchedule::AtExpiry => true,
+        ReleaseSchedule::Linear(cliff_days) => {
+            !rules.auto_rollover && cliff_days <= rules.duration_days
+        }
+        ReleaseSchedule::Tranches(interval_days) => {
+            !rules.auto_rollover && interval_days > 0 && interval_days <= rules.duration_days
+        }
+    };
+    if !valid {
+        fail(
+            e,
+            CommitmentError::InvalidReleaseSchedule,
+            "validate_release_schedule",
+        );
+    }
+}
+
+fn read_vesting_claimed(e: &Env, commitment_id: &String) -> i128 {
+    read_persistent(e, &DataKey::VestingClaimed(commitment_id.clone())).unwrap_or(0)
+}
+
+/// Portion of the commitment's value (claimed plus still locked) released by its
+/// schedule as of now. Everything is released at expiry.
+fn vested_amount(e: &Env, commitment: &Commitment, claimed: i128) -> i128 {
+    let total = SafeMath::add(commitment.current_value, claimed);
+    let now = e.ledger().timestamp();
+    if now >= commitment.expires_at {
+        return total;
+    }
+    let elapsed = now.saturating_sub(commitment.created_at);
+    let term = commitment.expires_at - commitment.created_at;
+    match commitment.rules.release_schedule {
+        ReleaseSchedule::AtExpiry => 0,
+        ReleaseSchedule::Linear(cliff_days) => {
+            if elapsed < TimeUtils::days_to_seconds(cliff_days) {
+                0
+            } else {
+                pro_rata(total, elapsed as i128, term as i128)
+            }
+        }
+        ReleaseSchedule::Tranches(interval_days) => {
+            let interval = TimeUtils::days_to_seconds(interval_days);
+            let tranches = term.div_ceil(interval);
+            pro_rata(total, (elapsed / interval) as i128, tranches as i128)
+        }
+    }
+}
+
+fn vesting_status(e: &Env, commitment: &Commitment) -> VestingStatus {
+    let claimed_amount = read_vesting_claimed(e, &commitment.commitment_id);
+    if !commitment.status.is_open() {
+        return VestingStatus {
+            claimed_amount,
+            vested_amount: claimed_amount,
+            releasable: 0,
+        };
+    }
+    let vested_amount = vested_amount(e, commitment, claimed_amount);
+    VestingStatus {
+        claimed_amount,
+        vested_amount,
+        releasable: SafeMath::sub(vested_amount, claimed_amount)
+            .min(commitment.current_value)
+            .max(0),
+    }
+}
+
+// ─── Operator helpers ─────────────────────────────────────────────────────────
+
+/// What an operator may do on an owner's behalf, and until when.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OperatorApproval {
+    pub scopes: Vec<OperatorScope>,
+    pub expires_at: Option<u64>, // ledger timestamp after which the approval lapses
+}
+
+/// Build an approval, rejecting empty scopes, self-approval and past expiries.
+fn new_operator_approval(
+    e: &Env,
+    owner: &Address,
+    operator: &Address,
+    scopes: Vec<OperatorScope>,
+    expires_at: Option<u64>,
+) -> Result<OperatorApproval, CommitmentError> {
+    if scopes.is_empty() || owner == operator {
+        return Err(CommitmentError::InvalidOperatorApproval);
+    }
+    if matches!(expires_at, Some(at) if at <= e.ledger().timestamp()) {
+        return Err(CommitmentError::InvalidOperatorApproval);
+    }
+    Ok(OperatorApproval { scopes, expires_at })
+}
+
+fn approval_allows(e: &Env, approval: Option<OperatorApproval>, scope: OperatorScope) -> bool {
+    approval.is_some_and(|approval| {
+        approval.scopes.contains(scope)
+            && approval
+                .expires_at
+                .is_none_or(|at| e.ledger().timestamp() < at)
+    })
+}
+
+/// Whether `operator` may act on `commitment` within `scope`: approved by the current
+/// owner for this commitment or for all of the owner's commitments.
+fn is_operator_for(
+    e: &Env,
+    commitment: &Commitment,
+    operator: &Address,
+    scope: OperatorScope,
+) -> bool {
+    let for_commitment = DataKey::CommitmentOperator(
+        commitment.commitment_id.clone(),
+        commitment.owner.clone(),
+        operator.clone(),
+    );
+    let for_owner = DataKey::OwnerOperator(commitment.owner.clone(), operator.clone());
+    approval_allows(e, read_persistent(e, &for_commitment), scope)
+        || approval_allows(e, read_persistent(e, &for_owner), scope)
+}
+
+// ─── Emergency helpers ────────────────────────────────────────────────────────
+
+fn read_penalty_shares_claimed(e: &Env, asset: &Address) -> i128 {
+    e.storage()
+        .instance()
+        .get::<_, i128>(&DataKey::PenaltySharesClaimed(asset.clone()))
+        .unwrap_or(0)
+}
+
+/// Balance of `asset` the contract owes: value locked in open commitments (sponsored
+/// positions included), the treasury's unclaimed penalties and redistributed penalties
+/// not yet claimed by commitments. Read from counters, so the cost does not grow with
+/// the number of commitments.
+fn tracked_asset_liabilities(e: &Env, asset: &Address) -> i128 {
+    let account = read_penalty_account(e, asset);
+    let unclaimed_shares =
+        SafeMath::sub(account.redistributed, read_penalty_shares_claimed(e, asset)).max(0);
+    SafeMath::add(
+        SafeMath::add(read_asset_value_locked(e, asset), account.treasury_balance),
+        unclaimed_shares,
+    )
+}
+
+// ─── Upgrade helpers ──────────────────────────────────────────────────────────
+
+/// Contract version, bumped with each release that needs a `migrate` step:
+/// 1 - version tracking and upgrade entry points
+pub const CURRENT_VERSION: u32 = 1;
+
+/// Mirror of `time_lock::ActionType`.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TimeLockActionType {
+    AdminChange = 0,
+    ParameterChange = 1,
+    Upgrade = 2,
+    FeeChange = 3,
+}
+
+/// Mirror of `time_lock::QueuedAction`, decoded from cross-contract reads.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TimeLockAction {
+    pub id: u64,
+    pub action_type: TimeLockActionType,
+    pub target: Address,
+    pub data: String,
+    pub queued_at: u64,
+    pub executable_at: u64,
+    pub executed: bool,
+    pub cancelled: bool,
+}
+
+fn read_version(e: &Env) -> u32 {
+    e.storage()
+        .instance()
+        .get::<_, u32>(&DataKey::Version)
+        .unwrap_or(0)
+}
+
+fn require_valid_wasm_hash(e: &Env, wasm_hash: &BytesN<32>) {
+    if *wasm_hash == BytesN::from_array(e, &[0; 32]) {
+        fail(e, CommitmentError::InvalidWasmHash, "upgrade");
+    }
+}
+
+/// Lowercase hex of `bytes`, the form upgrade hashes take in time_lock action data.
+fn hex_string(e: &Env, bytes: &BytesN<32>) -> String {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    let mut buf = [0u8; 64];
+    for (i, byte) in bytes.to_array().iter().enumerate() {
+        buf[2 * i] = HEX[(byte >> 4) as usize];
+        buf[2 * i + 1] = HEX[(byte & 0x0f) as usize];
+    }
+    String::from_bytes(e, &buf)
+}
+
+/// When a time lock is configured, require `action_id` to name an executed, not yet
+/// consumed action of `action_type` targeting this contract with `data`, and mark it
+/// consumed. Without a time lock this is a no-op and admin auth alone applies.
+fn require_time_lock_approval(
+    e: &Env,
+    action_id: Option<u64>,
+    action_type: TimeLockActionType,
+    data: &String,
+    context: &str,
+) {
+    let time_lock = match e.storage().instance().get::<_, Address>(&DataKey::TimeLock) {
+        Some(time_lock) => time_lock,
+        None => return,
+    };
+    let action_id = match action_id {
+        Some(action_id) => action_id,
+        None => fail(e, CommitmentError::TimeLockNotApproved, context),
+    };
+    if e.storage()
+        .persistent()
+        .has(&DataKey::TimeLockActionUsed(action_id))
+    {
+        fail(e, CommitmentError::TimeLockNotApproved, context);
+    }
+    let action = e.invoke_contract::<TimeLockAction>(
+        &time_lock,
+        &Symbol::new(e, "get_action"),
+        Vec::from_array(e, [action_id.into_val(e)]),
+    );
+    if !action.executed
+        || action.cancelled
+        || action.action_type != action_type
+        || action.target != e.current_contract_address()
+        || action.data != *data
+    {
+        fail(e, CommitmentError::TimeLockNotApproved, context);
+    }
+    e.storage()
+        .persistent()
+        .set(&DataKey::TimeLockActionUsed(action_id), &true);
+}
+
+// ─── Storage helpers ──────────────────────────────────────────────────────────
+//
+// Per-commitment data lives in persistent storage so the instance entry stays bounded.
+// Every persistent read or write tops the entry's TTL back up; `extend_ttl` lets the
+// admin refresh commitments that have not been touched for a while.
+
+/// Ledgers per day at ~5s close time.
+const DAY_IN_LEDGERS: u32 = 17_280;
+/// TTL that per-commitment persistent entries are extended to.
+const COMMITMENT_TTL_EXTEND_TO: u32 = 120 * DAY_IN_LEDGERS;
+/// Remaining TTL below which an entry is extended on access.
+const COMMITMENT_TTL_THRESHOLD: u32 = COMMITMENT_TTL_EXTEND_TO - DAY_IN_LEDGERS;
+
+/// Layout of stored commitment records:
+/// 0 - instance storage, string status and commitment type
+/// 1 - persistent storage, string status and commitment type
+/// 2 - persistent storage, `CommitmentStatus` / `CommitmentType` enums
+/// 3 - secondary status and asset indexes
+/// 4 - early-exit penalty schedule in `CommitmentRules`
+/// 5 - auto-rollover flag in `CommitmentRules`
+/// 6 - release schedule in `CommitmentRules`
+pub const STORAGE_VERSION: u32 = 6;
+
+fn read_storage_version(e: &Env) -> u32 {
+    e.storage()
+        .instance()
+        .get::<_, u32>(&DataKey::StorageVersion)
+        .unwrap_or(0)
+}
+
+fn extend_persistent_ttl(e: &Env, key: &DataKey) {
+    e.storage()
+        .persistent()
+        .extend_ttl(key, COMMITMENT_TTL_THRESHOLD, COMMITMENT_TTL_EXTEND_TO);
+}
+
+fn read_persistent<V: TryFromVal<Env, Val>>(e: &Env, key: &DataKey) -> Option<V> {
+    let value = e.storage().persistent().get::<_, V>(key);
+    if value.is_some() {
+        extend_persistent_ttl(e, key);
+    }
+    value
+}
+
+fn write_persistent<V: IntoVal<Env, Val>>(e: &Env, key: &DataKey, value: &V) {
+    e.storage().persistent().set(key, value);
+    extend_persistent_ttl(e, key);
+}
+
+// Entries not yet moved by `migrate_storage` are still served from instance storage.
+
+fn read_commitment(e: &Env, commitment_id: &String) -> Option<Commitment> {
+    let key = DataKey::Commitment(commitment_id.clone());
+    read_persistent::<Val>(e, &key)
+        .or_else(|| e.storage().instance().get::<_, Val>(&key))
+        .map(|raw| decode_commitment(e, &raw))
+}
+
+/// Decode a stored commitment, upgrading records written by earlier storage versions.
+fn decode_commitment(e: &Env, raw: &Val) -> Commitment {
+    // Decoding into a struct with a different field count traps instead of returning an
+    // error, so records are told apart by the fields their rules carry.
+    let rules = Map::<Symbol, Val>::try_from_val(e, raw)
+        .ok()
+        .and_then(|fields| fields.get(Symbol::new(e, "rules")))
+        .and_then(|rules| Map::<Symbol, Val>::try_from_val(e, &rules).ok());
+    let has_rule = |name: &str| {
+        rules
+            .as_ref()
+            .is_some_and(|rules| rules.contains_key(Symbol::new(e, name)))
+    };
+    if has_rule("release_schedule") {
+        return Commitment::try_from_val(e, raw)
+            .unwrap_or_else(|_| fail(e, CommitmentError::InvalidStatus, "decode_commitment"));
+    }
+    if has_rule("auto_rollover") {
+        return CommitmentV5::try_from_val(e, raw)
+            .unwrap_or_else(|_| fail(e, CommitmentError::InvalidStatus, "decode_commitment"))
+            .upgrade();
+    }
+    if has_rule("penalty_schedule") {
+        return CommitmentV4::try_from_val(e, raw)
+            .unwrap_or_else(|_| fail(e, CommitmentError::InvalidStatus, "decode_commitment"))
+            .upgrade()
+            .upgrade();
+    }
+    if let Ok(commitment) = CommitmentV2::try_from_val(e, raw) {
+        return commitment.upgrade().upgrade().upgrade();
+    }
+    CommitmentV1::try_from_val(e, raw)
+        .unwrap_or_else(|_| fail(e, CommitmentError::InvalidStatus, "decode_commitment"))
+        .upgrade(e)
+        .upgrade()
+        .upgrade()
+        .upgrade()
+}
+
+fn set_commitment(e: &Env, commitment: &Commitment) {
+    write_persistent(
+        e,
+        &DataKey::Commitment(commitment.commitment_id.clone()),
+        commitment,
+    );
+    sync_penalty_share(e, commitment);
+}
+
+fn has_commitment(e: &Env, commitment_id: &String) -> bool {
+    let key = DataKey::Commitment(commitment_id.clone());
+    e.storage().persistent().has(&key) || e.storage().instance().has(&key)
+}
+
+fn read_owner_commitments(e: &Env, owner: &Address) -> Vec<String> {
+    let key = DataKey::OwnerCommitments(owner.clone());
+    read_persistent::<Vec<String>>(e, &key)
+        .or_else(|| e.storage().instance().get::<_, Vec<String>>(&key))
+        .unwrap_or(Vec::new(e))
+}
+
+fn set_owner_commitments(e: &Env, owner: &Address, commitment_ids: &Vec<String>) {
+    write_persistent(e, &DataKey::OwnerCommitments(owner.clone()), commitment_ids);
+}
+
+// Secondary indexes are dense position -> id lists with an id -> position reverse entry,
+// so an id is removed by moving the last entry into its slot. Order is not stable.
+
+/// Upper bound on commitments returned by one `list_commitments` call.
+const MAX_PAGE_SIZE: u32 = 15;
+/// Upper bound on candidates examined by one `list_commitments` call. A candidate costs
+/// up to two ledger reads (index entry and commitment), which keeps a page within the
+/// per-transaction read limit.
+const MAX_PAGE_SCAN: u64 = 15;
+
+fn index_len(e: &Env, index: &CommitmentIndex) -> u64 {
+    read_persistent::<u64>(e, &DataKey::IndexLen(index.clone())).unwrap_or(0)
+}
+
+fn index_get(e: &Env, index: &CommitmentIndex, position: u64) -> Option<String> {
+    read_persistent::<String>(e, &DataKey::IndexEntry(index.clone(), position))
+}
+
+fn index_insert(e: &Env, index: &CommitmentIndex, commitment_id: &String) {
+    let pos_key = DataKey::IndexPos(index.clone(), commitment_id.clone());
+    if e.storage().persistent().has(&pos_key) {
+        return;
+    }
+    let len = index_len(e, index);
+    write_persistent(e, &DataKey::IndexEntry(index.clone(), len), commitment_id);
+    write_persistent(e, &pos_key, &len);
+    write_persistent(e, &DataKey::IndexLen(index.clone()), &(len + 1));
+}
+
+fn index_remove(e: &Env, index: &CommitmentIndex, commitment_id: &String) {
+    let pos_key = DataKey::IndexPos(index.clone(), commitment_id.clone());
+    let position = match e.storage().persistent().get::<_, u64>(&pos_key) {
+        Some(position) => position,
+        None => return,
+    };
+    let last = index_len(e, index) - 1;
+    if position != last {
+        if let Some(moved_id) = index_get(e, index, last) {
+            write_persistent(e, &DataKey::IndexEntry(index.clone(), position), &moved_id);
+            write_persistent(e, &DataKey::IndexPos(index.clone(), moved_id), &position);
+        }
+    }
+    let storage = e.storage().persistent();
+    storage.remove(&DataKey::IndexEntry(index.clone(), last));
+    storage.remove(&pos_key);
+    write_persistent(e, &DataKey::IndexLen(index.clone()), &last);
+}
+
+fn extend_index_ttl(e: &Env, index: &CommitmentIndex, commitment_id: &String) {
+    let pos_key = DataKey::IndexPos(index.clone(), commitment_id.clone());
+    if let Some(position) = read_persistent::<u64>(e, &pos_key) {
+        extend_persistent_ttl(e, &DataKey::IndexEntry(index.clone(), position));
+        extend_persistent_ttl(e, &DataKey::IndexLen(index.clone()));
+    }
+}
+
+fn index_commitment(e: &Env, commitment: &Commitment) {
+    index_insert(
+        e,
+        &CommitmentIndex::Status(commitment.status),
+        &commitment.commitment_id,
+    );
+    if commitment.status.is_open() {
+        index_insert(
+            e,
+            &CommitmentIndex::Asset(commitment.asset_address.clone()),
+            &commitment.commitment_id,
+        );
+    }
+}
+
+/// Change a commitment's status and move it to the matching status index; a closing
+/// commitment also leaves its asset index. The caller still persists the commitment.
+fn set_status(e: &Env, commitment: &mut Commitment, status: CommitmentStatus) {
+    if commitment.status == status {
+        return;
+    }
+    index_remove(
+        e,
+        &CommitmentIndex::Status(commitment.status),
+        &commitment.commitment_id,
+    );
+    index_insert(
+        e,
+        &CommitmentIndex::Status(status),
+        &commitment.commitment_id,
+    );
+    if commitment.status.is_open() && !status.is_open() {
+        index_remove(
+            e,
+            &CommitmentIndex::Asset(commitment.asset_address.clone()),
+            &commitment.commitment_id,
+        );
+    }
+    commitment.status = status;
+}
+
+fn read_grace_deadline(e: &Env, commitment_id: &String) -> Option<u64> {
+    read_persistent::<u64>(e, &DataKey::GraceDeadline(commitment_id.clone()))
+}
+
+fn set_grace_deadline(e: &Env, commitment_id: &String, deadline: u64) {
+    write_persistent(e, &DataKey::GraceDeadline(commitment_id.clone()), &deadline);
+}
+
+fn remove_grace_deadline(e: &Env, commitment_id: &String) {
+    e.storage()
+        .persistent()
+        .remove(&DataKey::GraceDeadline(commitment_id.clone()));
+}
+
+/// Rewrite one commitment at the current layout (persistent storage, typed status and type,
+/// penalty schedule, auto-rollover flag) and add it to the secondary indexes.
+/// Entries already written to persistent storage since the upgrade take precedence over
+/// the instance copy. Returns `true` if the commitment exists.
+fn migrate_commitment_entry(e: &Env, commitment_id: &String) -> bool {
+    let commitment = match read_commitment(e, commitment_id) {
+        Some(commitment) => commitment,
+        None => return false,
+    };
+    set_commitment(e, &commitment);
+    index_commitment(e, &commitment);
+    let key = DataKey::Commitment(commitment_id.clone());
+    if e.storage().instance().has(&key) {
+        e.storage().instance().remove(&key);
+    }
+
+    let owner_key = DataKey::OwnerCommitments(commitment.owner);
+    if let Some(commitment_ids) = e.storage().instance().get::<_, Vec<String>>(&owner_key) {
+        if !e.storage().persistent().has(&owner_key) {
+            write_persistent(e, &owner_key, &commitment_ids);
+        }
+        e.storage().instance().remove(&owner_key);
+    }
+    true
+}
+
+/// A loss breach only counts as a violation once the grace window (if any) has ended.
+fn loss_violation_confirmed(e: &Env, commitment: &Commitment, loss_breached: bool) -> bool {
+    if !loss_breached {
+        return false;
+    }
+    if commitment.rules.grace_period_days == 0 {
+        return true;
+    }
+    match read_grace_deadline(e, &commitment.commitment_id) {
+        Some(deadline) => e.ledger().timestamp() >= deadline,
+        None => false,
+    }
+}
+
+/// Run the loss/grace/violation state machine against `valuation` and persist the
+/// commitment. Shared by `update_value` and oracle-driven `revalue`.
+/// Returns `true` if the commitment was moved to `Violated`.
+fn apply_valuation(e: &Env, mut commitment: Commitment, valuation: i128) -> bool {
+    // Violation detection
+    let loss_percent = if commitment.amount > 0 {
+        (commitment.amount - valuation) * 100 / commitment.amount
+    } else {
+        0
+    };
+    let loss_breached = loss_percent > commitment.rules.max_loss_percent as i128;
+    let now = e.ledger().timestamp();
+
+    // Grace period state machine:
+    // active --breach--> at_risk --still breached at deadline--> violated
+    //    ^                  |
+    //    +----recovered-----+
+    // Without a grace period a breach goes straight to violated.
+    let mut violated = false;
+    if loss_breached && commitment.status == CommitmentStatus::Active {
+        if commitment.rules.grace_period_days == 0 {
+            violated = true;
+        } else {
+            let deadline = now + TimeUtils::days_to_seconds(commitment.rules.grace_period_days);
+            set_status(e, &mut commitment, CommitmentStatus::AtRisk);
+            set_grace_deadline(e, &commitment.commitment_id, deadline);
+            e.events().publish(
+                (symbol_short!("AtRisk"), commitment.commitment_id.clone()),
+                (
+                    loss_percent,
+                    commitment.rules.max_loss_percent,
+                    deadline,
+                    now,
+                ),
+            );
+        }
+    } else if loss_breached {
+        // Already at risk: violated once the grace window has ended
+        violated = loss_violation_confirmed(e, &commitment, true);
+    } else if commitment.status == CommitmentStatus::AtRisk {
+        set_status(e, &mut commitment, CommitmentStatus::Active);
+        remove_grace_deadline(e, &commitment.commitment_id);
+        e.events().publish(
+            (symbol_short!("Recovered"), commitment.commitment_id.clone()),
+            (loss_percent, now),
+        );
+    }
+
+    if violated {
+        set_status(e, &mut commitment, CommitmentStatus::Violated);
+        remove_grace_deadline(e, &commitment.commitment_id);
+        e.events().publish(
+            (symbol_short!("Violated"), commitment.commitment_id.clone()),
+            (
+                loss_percent,
+                commitment.rules.max_loss_percent,
+                e.ledger().timestamp(),
+            ),
+        );
+    }
+
+    set_commitment(e, &commitment);
+    violated
+}
+
+/// Persist a new `current_value`, check the loss limit against `valuation` and adjust
+/// TotalValueLocked. Returns `true` if the commitment was moved to `Violated`.
+fn apply_value_update(
+    e: &Env,
+    mut commitment: Commitment,
+    new_value: i128,
+    valuation: i128,
+) -> bool {
+    let old_value = commitment.current_value;
+    commitment.current_value = new_value;
+    let commitment_id = commitment.commitment_id.clone();
+    let basket = read_basket_legs(e, &commitment_id).is_some();
+    let owner = commitment.owner.clone();
+    let asset_address = commitment.asset_address.clone();
+    let violated = apply_valuation(e, commitment, valuation);
+
+    // Update TVL
+    let current_tvl = e
+        .storage()
+        .instance()
+        .get::<_, i128>(&DataKey::TotalValueLocked)
+        .unwrap_or(0);
+    e.storage().instance().set(
+        &DataKey::TotalValueLocked,
+        &(current_tvl - old_value + new_value),
+    );
+    // Basket values are quoted in another asset; their locked leg amounts do not change
+    if !basket {
+        adjust_asset_value_locked(e, &owner, &asset_address, new_value - old_value);
+    }
+
+    e.events().publish(
+        (symbol_short!("ValUpd"), commitment_id),
+        (old_value, new_value, violated, e.ledger().timestamp()),
+    );
+    violated
+}
+
+/// Seconds after expiry during which only the owner (or a `Renew` operator) can act on
+/// an auto-rollover commitment: pay it out with `claim_vested`, opt out with
+/// `set_auto_rollover`, or roll it over with `renew`. Afterwards anyone can settle it,
+/// which rolls it over.
+pub const ROLLOVER_OWNER_WINDOW: u64 = 86_400;
+
+fn read_keeper_bounty_bps(e: &Env) -> u32 {
+    e.storage()
+        .instance()
+        .get::<_, u32>(&DataKey::KeeperBountyBps)
+        .unwrap_or(0)
+}
+
+/// Settle one expired commitment: pay out the owner (less any fee rebate and keeper
+/// bounty) and mark the NFT settled. Shared by `settle`, `batch_settle` and
+/// `claim_vested`; the caller holds the reentrancy guard. Returns the owner's payout
+/// (zero when the commitment rolls over).
+///
+/// With `rollover` an opted-in commitment restarts instead, once `ROLLOVER_OWNER_WINDOW`
+/// has passed; the owner's own `claim_vested` passes `false` and is paid out.
+///
+/// Eligibility is checked before any state changes, so an `Err` leaves storage untouched
+/// (apart from allocations already recalled from their pools) and a batch can skip the id.
+fn settle_commitment(
+    e: &Env,
+    commitment_id: &String,
+    keeper: Option<&Address>,
+    rollover: bool,
+) -> Result<i128, CommitmentError> {
+    // CHECKS: Get and validate commitment
+    let mut commitment =
+        read_commitment(e, commitment_id).ok_or(CommitmentError::CommitmentNotFound)?;
+
+    // Verify commitment is expired
+    if e.ledger().timestamp() < commitment.expires_at {
+        return Err(CommitmentError::NotExpired);
+    }
+
+    // Verify commitment is open (an AtRisk commitment is still live)
+    if !commitment.status.is_open() {
+        return Err(CommitmentError::NotActive);
+    }
+
+    let nft_contract = e
+        .storage()
+        .instance()
+        .get::<_, Address>(&DataKey::NftContract)
+        .ok_or(CommitmentError::NotInitialized)?;
+
+    // Opted-in commitments restart for another term instead of paying out. Fee
+    // consequences and keeper bounties only apply to payouts.
+    if rollover && commitment.rules.auto_rollover && commitment.current_value > 0 {
+        if e.ledger().timestamp() < commitment.expires_at + ROLLOVER_OWNER_WINDOW {
+            return Err(CommitmentError::RolloverWindowOpen);
+        }
+        let rules = commitment.rules.clone();
+        renew_commitment(e, &mut commitment, rules, &nft_contract)?;
+        return Ok(0);
+    }
+
+    // Recall allocated funds so the payout is made in full
+    if unwind_allocations(e, &commitment) > 0 {
+        return Err(CommitmentError::AllocationNotReturned);
+    }
+
+    // CHECKS: Compare attested fees with min_fee_threshold (read-only external call)
+    let fee_rebate = match run_fee_check(e, &commitment) {
+        Some(check) if check.shortfall > 0 => {
+            let action = e
+                .storage()
+                .instance()
+                .get::<_, FeeShortfallAction>(&DataKey::FeeShortfallAction)
+                .unwrap_or(FeeShortfallAction::Flag);
+            match action {
+                FeeShortfallAction::Flag => 0,
+                FeeShortfallAction::Rebate => check.shortfall.min(commitment.current_value.max(0)),
+            }
+        }
+        _ => 0,
+    };
+
+    // EFFECTS: Update state before external calls
+    let locked_value = commitment.current_value;
+    let settlement_amount = SafeMath::sub(locked_value, fee_rebate);
+    let keeper_bounty = match keeper {
+        Some(keeper) if *keeper != commitment.owner && settlement_amount > 0 => {
+            let bounty = fee_from_bps(settlement_amount, read_keeper_bounty_bps(e));
+            (bounty > 0).then_some((keeper, bounty))
+        }
+        _ => None,
+    };
+    let owner_amount = match keeper_bounty {
+        Some((_, bounty)) => SafeMath::sub(settlement_amount, bounty),
+        None => settlement_amount,
+    };
+    let bounty_amount = keeper_bounty.map_or(0, |(_, bounty)| bounty);
+
+    // (asset, owner payout, keeper bounty); basket legs are paid back in their own assets,
+    // each reduced by the same share as the basket's value
+    let mut payouts = Vec::new(e);
+    match read_basket_legs(e, commitment_id) {
+        Some(legs) => {
+            for leg in legs.iter() {
+                payouts.push_back((
+                    leg.asset_address.clone(),
+                    pro_rata(leg.amount, owner_amount, locked_value),
+                    pro_rata(leg.amount, bounty_amount, locked_value),
+                ));
+                adjust_asset_value_locked(e, &commitment.owner, &leg.asset_address, -leg.amount);
+            }
+        }
+        None => {
+            payouts.push_back((
+                commitment.asset_address.clone(),
+                owner_amount,
+                bounty_amount,
+            ));
+            adjust_asset_value_locked(
+                e,
+                &commitment.owner,
+                &commitment.asset_address,
+                -locked_value,
+            );
+        }
+    }
+    set_status(e, &mut commitment, CommitmentStatus::Settled);
+    set_commitment(e, &commitment);
+    remove_grace_deadline(e, commitment_id);
+
+    // Decrease total value locked (any fee rebate stays in the contract, unlocked)
+    let current_tvl = e
+        .storage()
+        .instance()
+        .get::<_, i128>(&DataKey::TotalValueLocked)
+        .unwrap_or(0);
+    let new_tvl = current_tvl - locked_value;
+    e.storage()
+        .instance()
+        .set(&DataKey::TotalValueLocked, &new_tvl);
+
+    // INTERACTIONS: External calls (token transfer, NFT settlement)
+    // Transfer assets back to owner, and the bounty to the keeper
+    let contract_address = e.current_contract_address();
+    for (asset, owner_payout, bounty) in payouts.iter() {
+        let token_client = token::Client::new(e, &asset);
+        if owner_payout > 0 {
+            token_client.transfer(&contract_address, &commitment.owner, &owner_payout);
+        }
+        if let Some((keeper, _)) = keeper_bounty.filter(|_| bounty > 0) {
+            token_client.transfer(&contract_address, keeper, &bounty);
+        }
+    }
+    if let Some((keeper, bounty)) = keeper_bounty {
+        e.events().publish(
+            (symbol_short!("KeeperPay"), commitment_id.clone()),
+            (keeper.clone(), bounty, e.ledger().timestamp()),
+        );
+    }
+
+    // Call NFT contract to mark NFT as settled (pass self as caller for access control)
+    let mut args = Vec::new(e);
+    args.push_back(contract_address.into_val(e));
+    args.push_back(commitment.nft_token_id.into_val(e));
+    e.invoke_contract::<()>(&nft_contract, &Symbol::new(e, "settle"), args);
+
+    if fee_rebate > 0 {
+        e.events().publish(
+            (symbol_short!("FeeRebate"), commitment_id.clone()),
+            (fee_rebate, e.ledger().timestamp()),
+        );
+    }
+
+    // Emit settlement event
+    e.events().publish(
+        (symbol_short!("Settled"), commitment_id.clone()),
+        (owner_amount, e.ledger().timestamp()),
+    );
+    Ok(owner_amount)
+}
+
+/// Restart an expired commitment for another term under `rules`. The id, NFT and locked
+/// funds carry over; the current value becomes the new principal.
+///
+/// The NFT is re-stamped before anything is written, so a failed renewal leaves the
+/// commitment as it was.
+fn renew_commitment(
+    e: &Env,
+    commitment: &mut Commitment,
+    rules: CommitmentRules,
+    nft_contract: &Address,
+) -> Result<(), CommitmentError> {
+    let now = e.ledger().timestamp();
+    let mut renewed = commitment.clone();
+    renewed.amount = renewed.current_value;
+    renewed.created_at = now;
+    renewed.expires_at = TimeUtils::calculate_expiration(e, rules.duration_days);
+    renewed.rules = rules;
+
+    // INTERACTIONS: Re-stamp the existing NFT instead of settling it and minting a new one
+    call_nft_renew(e, nft_contract, &renewed)?;
+
+    // EFFECTS: Restart the term; the locked value (and TVL) is unchanged
+    *commitment = renewed;
+    set_status(e, commitment, CommitmentStatus::Active);
+    set_commitment(e, commitment);
+    remove_grace_deadline(e, &commitment.commitment_id);
+    // The new term vests from scratch
+    e.storage()
+        .persistent()
+        .remove(&DataKey::VestingClaimed(commitment.commitment_id.clone()));
+
+    e.events().publish(
+        (symbol_short!("Renewed"), commitment.commitment_id.clone()),
+        (commitment.amount, commitment.expires_at, now),
+    );
+    Ok(())
+}
+
+fn require_no_reentrancy(e: &Env) {
+    let guard: bool = e
+        .storage()
+        .instance()
+        .get::<_, bool>(&DataKey::ReentrancyGuard)
+        .unwrap_or(false);
+
+    if guard {
+        fail(
+            e,
+            CommitmentError::ReentrancyDetected,
+            "require_no_reentrancy",
+        );
+    }
+}
+
+fn set_reentrancy_guard(e: &Env, value: bool) {
+    e.storage()
+        .instance()
+        .set(&DataKey::ReentrancyGuard, &value);
+}
+
+/// Require that the caller is the admin stored in this contract.
+fn require_admin(e: &Env, caller: &Address) {
+    caller.require_auth();
+    let admin = e
+        .storage()
+        .instance()
+        .get::<_, Address>(&DataKey::Admin)
+        .unwrap_or_else(|| fail(e, CommitmentError::NotInitialized, "require_admin"));
+    if *caller != admin {
+        fail(e, CommitmentError::Unauthorized, "require_admin");
+    }
+}
+
+fn require_authorized_updater(e: &Env, caller: &Address) {
+    caller.require_auth();
+    let updaters: Vec<Address> = e
+        .storage()
+        .instance()
+        .get::<_, Vec<Address>>(&DataKey::AuthorizedUpdaters)
+        .unwrap_or(Vec::new(e));
+    if !updaters.contains(caller) {
+        fail(e, CommitmentError::NotAuthorizedUpdater, "Unauthorized");
+    }
+}
+
+fn add_authorized_updater(e: &Env, updater: &Address) {
+    let mut updaters: Vec<Address> = e
+        .storage()
+        .instance()
+        .get::<_, Vec<Address>>(&DataKey::AuthorizedUpdaters)
+        .unwrap_or(Vec::new(e));
+    if !updaters.contains(updater) {
+        updaters.push_back(updater.clone());
+        e.storage()
+            .instance()
+            .set(&DataKey::AuthorizedUpdaters, &updaters);
+    }
+}
+
+fn remove_authorized_updater(e: &Env, updater: &Address) {
+    let mut updaters: Vec<Address> = e
+        .storage()
+        .instance()
+        .get::<_, Vec<Address>>(&DataKey::AuthorizedUpdaters)
+        .unwrap_or(Vec::new(e));
+    if let Some(idx) = updaters.iter().position(|a| a == *updater) {
+        updaters.remove(idx as u32);
+        e.storage()
+            .instance()
+            .set(&DataKey::AuthorizedUpdaters, &updaters);
+    }
+}
+
+// ─── Pause helpers (free functions used by the contract impl) ─────────────────
+
+/// Pause the contract. Caller must be admin.
+pub fn pause(e: Env, caller: Address) {
+    require_admin(&e, &caller);
+    Pausable::pause(&e);
+}
+
+/// Unpause the contract. Caller must be admin.
+pub fn unpause(e: Env, caller: Address) {
+    require_admin(&e, &caller);
+    Pausable::unpause(&e);
+}
+
+/// Returns `true` if the contract is currently paused.
+pub fn is_paused(e: Env) -> bool {
+    Pausable::is_paused(&e)
+}
+
+#[contract]
+pub struct CommitmentCoreContract;
+
 #[contractimpl]
 impl CommitmentCoreContract {
     /// Validate commitment rules using shared utilities
-    fn validate_rules(e: &Env, rules: &CommitmentRules) {
+    fn validate_rules(rules: &CommitmentRules) {
         // Duration must be > 0
         Validation::require_valid_duration(rules.duration_days);
 
-        // Max loss percent must be between 0 and 100
-        Validation::require_valid_percent(rules.max_loss_percent);
+        // Max loss percent must be between 0 and 100
+        Validation::require_valid_percent(rules.max_loss_percent);
+    }
+
+    /// Generate unique commitment ID
+    /// Optimized: Uses counter to create unique ID efficiently
+    fn generate_commitment_id(e: &Env, counter: u64) -> String {
+        commitment_interface::types::commitment_id(e, counter)
+    }
+
+    /// Initialize the core commitment contract
+    pub fn initialize(e: Env, admin: Address, nft_contract: Address) {
+        // Check if already initialized
+        if e.storage().instance().has(&DataKey::Admin) {
+            fail(&e, CommitmentError::AlreadyInitialized, "initialize");
+        }
+
+        // Store admin and NFT contract address
+        e.storage().instance().set(&DataKey::Admin, &admin);
+        e.storage()
+            .instance()
+            .set(&DataKey::NftContract, &nft_contract);
+
+        // Initialize total commitments counter
+        e.storage()
+            .instance()
+            .set(&DataKey::TotalCommitments, &0u64);
+
+        // Initialize total value locked counter
+        e.storage()
+            .instance()
+            .set(&DataKey::TotalValueLocked, &0i128);
+
+        // Initialize paused state (default: not paused)
+        e.storage().instance().set(&Pausable::PAUSED_KEY, &false);
+
+        // Fresh deployments store commitments at the current layout from the start
+        e.storage()
+            .instance()
+            .set(&DataKey::StorageVersion, &STORAGE_VERSION);
+        e.storage()
+            .instance()
+            .set(&DataKey::Version, &CURRENT_VERSION);
+    }
+
+    /// Create a new commitment
+    ///
+    /// # Reentrancy Protection
+    /// This function uses checks-effects-interactions pattern:
+    /// 1. Checks: Validate inputs
+    /// 2. Effects: Update state (commitment storage, counters)
+    /// 3. Interactions: External calls (token transfer, NFT mint)
+    /// Reentrancy guard prevents recursive calls.
+    ///
+    /// # Formal Verification
+    /// **Preconditions:**
+    /// - `amount > 0`
+    /// - `rules.duration_days > 0`
+    /// - `rules.max_loss_percent <= 100`
+    /// - Contract is initialized
+    /// - `reentrancy_guard == false`
+    ///
+    /// **Postconditions:**
+    /// - Returns unique `commitment_id`
+    /// - `get_commitment(commitment_id).owner == owner`
+    /// - `get_commitment(commitment_id).amount == amount`
+    /// - `get_commitment(commitment_id).status == CommitmentStatus::Active`
+    /// - `get_total_commitments() == old(get_total_commitments()) + 1`
+    /// - `reentrancy_guard == false`
+    ///
+    /// **Invariants Maintained:**
+    /// - INV-1: Total commitments consistency
+    /// - INV-2: Commitment balance conservation
+    /// - INV-3: Owner commitment list consistency
+    /// - INV-4: Reentrancy guard invariant
+    ///
+    /// **Security Properties:**
+    /// - SP-1: Reentrancy protection
+    /// - SP-2: Access control
+    /// - SP-4: State consistency
+    /// - SP-5: Token conservation
+    pub fn create_commitment(
+        e: Env,
+        owner: Address,
+        amount: i128,
+        asset_address: Address,
+        rules: CommitmentRules,
+    ) -> String {
+        require_templates_not_enforced(&e, "create_commitment");
+        Self::create_commitment_internal(&e, &owner, &owner, amount, asset_address, rules, None)
+    }
+
+    /// Create a commitment funded by `funder` on behalf of `beneficiary`.
+    ///
+    /// `amount` is pulled from the funder; the beneficiary owns the commitment, its NFT,
+    /// settlement proceeds and exit rights. With `clawback_until` (a ledger timestamp no
+    /// later than `expires_at`), the funder can `clawback` the position until then, and
+    /// the beneficiary cannot exit early or withdraw before it.
+    pub fn create_commitment_for(
+        e: Env,
+        funder: Address,
+        beneficiary: Address,
+        amount: i128,
+        asset_address: Address,
+        rules: CommitmentRules,
+        clawback_until: Option<u64>,
+    ) -> String {
+        require_templates_not_enforced(&e, "create_commitment_for");
+        funder.require_auth();
+        if let Some(deadline) = clawback_until {
+            let expires_at = TimeUtils::calculate_expiration(&e, rules.duration_days);
+            if deadline <= e.ledger().timestamp() || deadline > expires_at {
+                fail(
+                    &e,
+                    CommitmentError::InvalidClawback,
+                    "create_commitment_for",
+                );
+            }
+        }
+
+        let sponsorship = Sponsorship {
+            funder: funder.clone(),
+            clawback_until,
+        };
+        let commitment_id = Self::create_commitment_internal(
+            &e,
+            &funder,
+            &beneficiary,
+            amount,
+            asset_address,
+            rules,
+            Some(sponsorship),
+        );
+
+        e.events().publish(
+            (symbol_short!("Sponsored"), commitment_id.clone(), funder),
+            (beneficiary, amount, clawback_until, e.ledger().timestamp()),
+        );
+        commitment_id
+    }
+
+    /// Create a commitment from a catalog template (see `set_template`).
+    ///
+    /// The commitment locks `amount` of the template's asset under the template's rules,
+    /// for `duration_days` within the template's bounds. Otherwise it behaves like
+    /// `create_commitment`, including asset limits and rate limiting.
+    pub fn create_commitment_from_template(
+        e: Env,
+        owner: Address,
+        template_id: Symbol,
+        amount: i128,
+        duration_days: u32,
+    ) -> String {
+        let template = read_template(&e, &template_id)
+            .filter(|template| template.enabled)
+            .unwrap_or_else(|| {
+                fail(
+                    &e,
+                    CommitmentError::TemplateUnavailable,
+                    "create_commitment_from_template",
+                )
+            });
+        if duration_days < template.min_duration_days || duration_days > template.max_duration_days
+        {
+            fail(
+                &e,
+                CommitmentError::DurationOutOfRange,
+                "create_commitment_from_template",
+            );
+        }
+
+        let mut rules = template.rules;
+        rules.duration_days = duration_days;
+        let commitment_id = Self::create_commitment_internal(
+            &e,
+            &owner,
+            &owner,
+            amount,
+            template.asset_address,
+            rules,
+            None,
+        );
+        write_persistent(
+            &e,
+            &DataKey::CommitmentTemplate(commitment_id.clone()),
+            &template_id,
+        );
+
+        e.events().publish(
+            (
+                symbol_short!("FromTmpl"),
+                commitment_id.clone(),
+                template_id,
+            ),
+            (owner, amount, duration_days, e.ledger().timestamp()),
+        );
+        commitment_id
+    }
+
+    /// Create a commitment owned by `owner`, funded from `funder`.
+    fn create_commitment_internal(
+        e: &Env,
+        funder: &Address,
+        owner: &Address,
+        amount: i128,
+        asset_address: Address,
+        rules: CommitmentRules,
+        sponsorship: Option<Sponsorship>,
+    ) -> String {
+        // Reentrancy protection
+        require_no_reentrancy(e);
+        set_reentrancy_guard(e, true);
+
+        // Check if contract is paused
+        Pausable::require_not_paused(e);
+        EmergencyControl::require_not_emergency(e);
+
+        // Rate limit: per-funder commitment creation
+        let fn_symbol = symbol_short!("create");
+        RateLimiter::check(e, funder, &fn_symbol);
+
+        // Validate amount > 0 using shared utilities
+        Validation::require_positive(amount);
+
+        // Validate rules
+        Self::validate_rules(&rules);
+        validate_penalty_schedule(e, &rules);
+        validate_release_schedule(e, &rules);
+
+        if let Err(err) = check_asset_limits(e, owner, &asset_address, amount, amount) {
+            set_reentrancy_guard(e, false);
+            fail(e, err, "create_commitment");
+        }
+
+        // CHECKS: Verify sufficient balance BEFORE any state modifications (CEI pattern)
+        check_sufficient_balance(e, funder, &asset_address, amount);
+
+        // OPTIMIZATION: Read both counters and NFT contract once to minimize storage operations
+        let (current_total, current_tvl, nft_contract) = {
+            let total = e
+                .storage()
+                .instance()
+                .get::<_, u64>(&DataKey::TotalCommitments)
+                .unwrap_or(0);
+            let tvl = e
+                .storage()
+                .instance()
+                .get::<_, i128>(&DataKey::TotalValueLocked)
+                .unwrap_or(0);
+            let nft = e
+                .storage()
+                .instance()
+                .get::<_, Address>(&DataKey::NftContract)
+                .unwrap_or_else(|| {
+                    set_reentrancy_guard(e, false);
+                    fail(e, CommitmentError::NotInitialized, "create_commitment")
+                });
+            (total, tvl, nft)
+        };
+
+        // Generate unique commitment ID using counter
+        let commitment_id = Self::generate_commitment_id(e, current_total);
+
+        // CHECKS: Validate commitment doesn't already exist
+        if has_commitment(e, &commitment_id) {
+            set_reentrancy_guard(e, false);
+            fail(e, CommitmentError::InvalidStatus, "create_commitment");
+        }
+
+        // EFFECTS: Update state before external calls
+        // Calculate expiration timestamp using shared utilities
+        let current_timestamp = TimeUtils::now(e);
+        let expires_at = TimeUtils::calculate_expiration(e, rules.duration_days);
+
+        // Create commitment data
+        let commitment = Commitment {
+            commitment_id: commitment_id.clone(),
+            owner: owner.clone(),
+            nft_token_id: 0, // Will be set after NFT mint
+            rules: rules.clone(),
+            amount,
+            asset_address: asset_address.clone(),
+            created_at: current_timestamp,
+            expires_at,
+            current_value: amount, // Initially same as amount
+            status: CommitmentStatus::Active,
+        };
+
+        // Store commitment data (before external calls)
+        set_commitment(e, &commitment);
+        index_commitment(e, &commitment);
+        if let Some(sponsorship) = sponsorship {
+            write_persistent(
+                e,
+                &DataKey::Sponsorship(commitment_id.clone()),
+                &sponsorship,
+            );
+        }
+
+        // Update owner's commitment list
+        let mut owner_commitments = read_owner_commitments(e, owner);
+        owner_commitments.push_back(commitment_id.clone());
+        set_owner_commitments(e, owner, &owner_commitments);
+
+        // OPTIMIZATION: Increment both counters using already-read values
+        e.storage()
+            .instance()
+            .set(&DataKey::TotalCommitments, &(current_total + 1));
+        e.storage()
+            .instance()
+            .set(&DataKey::TotalValueLocked, &(current_tvl + amount));
+        adjust_asset_value_locked(e, owner, &asset_address, amount);
+
+        // INTERACTIONS: External calls (token transfer, NFT mint)
+        // Transfer assets from the funder to contract
+        let contract_address = e.current_contract_address();
+        transfer_assets(e, funder, &contract_address, &asset_address, amount);
+
+        // Mint NFT
+        let nft_token_id = call_nft_mint(
+            e,
+            &nft_contract,
+            owner,
+            &commitment_id,
+            rules.duration_days,
+            rules.max_loss_percent,
+            rules.commitment_type,
+            amount,
+            &asset_address,
+        );
+
+        // Update commitment with NFT token ID
+        let mut updated_commitment = commitment;
+        updated_commitment.nft_token_id = nft_token_id;
+        set_commitment(e, &updated_commitment);
+
+        // Anchor the oracle mark so the first `revalue` measures the move since entry.
+        // A missing or stale price must not block creation; `revalue` anchors lazily.
+        if let Some(price_oracle) = e
+            .storage()
+            .instance()
+            .get::<_, Address>(&DataKey::PriceOracle)
+        {
+            if let Ok(price) = read_oracle_price(e, &price_oracle, &asset_address) {
+                set_price_mark(e, &commitment_id, &price);
+            }
+        }
+
+        // Clear reentrancy guard
+        set_reentrancy_guard(e, false);
+
+        // Emit creation event
+        e.events().publish(
+            (
+                symbol_short!("Created"),
+                commitment_id.clone(),
+                owner.clone(),
+            ),
+            (amount, rules, nft_token_id, e.ledger().timestamp()),
+        );
+        commitment_id
+    }
+
+    /// Store a new commitment holding `value` (principal `basis`) split off `original`,
+    /// with the same owner, rules and term, mint its NFT and emit `Created` for it. The
+    /// caller holds the reentrancy guard and has already taken the value off `original`.
+    fn create_split_part(
+        e: &Env,
+        original: &Commitment,
+        value: i128,
+        basis: i128,
+        nft_contract: &Address,
+    ) -> String {
+        let total = e
+            .storage()
+            .instance()
+            .get::<_, u64>(&DataKey::TotalCommitments)
+            .unwrap_or(0);
+        let commitment_id = Self::generate_commitment_id(e, total);
+        if has_commitment(e, &commitment_id) {
+            set_reentrancy_guard(e, false);
+            fail(e, CommitmentError::InvalidStatus, "split");
+        }
+
+        // EFFECTS
+        let mut commitment = Commitment {
+            commitment_id: commitment_id.clone(),
+            owner: original.owner.clone(),
+            nft_token_id: 0, // Will be set after NFT mint
+            rules: original.rules.clone(),
+            amount: basis,
+            asset_address: original.asset_address.clone(),
+            created_at: original.created_at,
+            expires_at: original.expires_at,
+            current_value: value,
+            status: CommitmentStatus::Active,
+        };
+        set_commitment(e, &commitment);
+        index_commitment(e, &commitment);
+        let mut owner_commitments = read_owner_commitments(e, &commitment.owner);
+        owner_commitments.push_back(commitment_id.clone());
+        set_owner_commitments(e, &commitment.owner, &owner_commitments);
+        e.storage()
+            .instance()
+            .set(&DataKey::TotalCommitments, &(total + 1));
+        if let Some(mark) =
+            read_persistent::<PriceMark>(e, &DataKey::PriceMark(original.commitment_id.clone()))
+        {
+            write_persistent(e, &DataKey::PriceMark(commitment_id.clone()), &mark);
+        }
+        if let Some(template_id) = read_commitment_template(e, &original.commitment_id) {
+            write_persistent(
+                e,
+                &DataKey::CommitmentTemplate(commitment_id.clone()),
+                &template_id,
+            );
+        }
+
+        // INTERACTIONS: The NFT counts its term from now, so it is minted for the whole
+        // days left and then stamped with the original's exact expiry
+        let remaining_days = (original.expires_at - e.ledger().timestamp()) / 86_400;
+        commitment.nft_token_id = call_nft_mint(
+            e,
+            nft_contract,
+            &commitment.owner,
+            &commitment_id,
+            remaining_days as u32,
+            commitment.rules.max_loss_percent,
+            commitment.rules.commitment_type,
+            basis,
+            &commitment.asset_address,
+        );
+        call_nft_set_expires_at(
+            e,
+            nft_contract,
+            commitment.nft_token_id,
+            original.expires_at,
+        );
+        set_commitment(e, &commitment);
+
+        e.events().publish(
+            (
+                symbol_short!("Created"),
+                commitment_id.clone(),
+                commitment.owner.clone(),
+            ),
+            (
+                basis,
+                commitment.rules,
+                commitment.nft_token_id,
+                e.ledger().timestamp(),
+            ),
+        );
+        commitment_id
+    }
+
+    /// Create a commitment locking several assets with target weights.
+    ///
+    /// The commitment is denominated in `quote_asset`: `amount` and `current_value` are
+    /// the basket's aggregate value in the quote asset, so loss limits and `revalue` apply
+    /// to the basket as a whole. Each leg's value must be within
+    /// `BASKET_WEIGHT_TOLERANCE_BPS` of its `weight_bps` at the oracle's current prices.
+    /// Settlement and early exit pay every leg back in its own asset.
+    pub fn create_basket_commitment(
+        e: Env,
+        owner: Address,
+        quote_asset: Address,
+        legs: Vec<BasketLeg>,
+        rules: CommitmentRules,
+    ) -> String {
+        require_templates_not_enforced(&e, "create_basket_commitment");
+
+        // Reentrancy protection
+        require_no_reentrancy(&e);
+        set_reentrancy_guard(&e, true);
+
+        // Check if contract is paused
+        Pausable::require_not_paused(&e);
+        EmergencyControl::require_not_emergency(&e);
+
+        // Rate limit: shares the per-owner creation limit with `create_commitment`
+        let fn_symbol = symbol_short!("create");
+        RateLimiter::check(&e, &owner, &fn_symbol);
+
+        // Validate rules and legs
+        Self::validate_rules(&rules);
+        validate_penalty_schedule(&e, &rules);
+        validate_release_schedule(&e, &rules);
+        // Legs vest together only at expiry
+        if rules.release_schedule != ReleaseSchedule::AtExpiry {
+            set_reentrancy_guard(&e, false);
+            fail(
+                &e,
+                CommitmentError::BasketNotSupported,
+                "create_basket_commitment",
+            );
+        }
+        if let Err(err) = validate_basket_legs(&legs) {
+            set_reentrancy_guard(&e, false);
+            fail(&e, err, "create_basket_commitment");
+        }
+
+        // CHECKS: Value the legs in the quote asset and check them against their weights
+        let price_oracle = e
+            .storage()
+            .instance()
+            .get::<_, Address>(&DataKey::PriceOracle)
+            .unwrap_or_else(|| {
+                set_reentrancy_guard(&e, false);
+                fail(
+                    &e,
+                    CommitmentError::OracleNotConfigured,
+                    "create_basket_commitment",
+                )
+            });
+        let quote_price =
+            read_oracle_price(&e, &price_oracle, &quote_asset).unwrap_or_else(|err| {
+                set_reentrancy_guard(&e, false);
+                fail(&e, err, "create_basket_commitment")
+            });
+        let values =
+            basket_leg_values(&e, &price_oracle, &quote_price, &legs).unwrap_or_else(|err| {
+                set_reentrancy_guard(&e, false);
+                fail(&e, err, "create_basket_commitment")
+            });
+        let amount = values.iter().fold(0i128, SafeMath::add);
+        if let Err(err) = validate_basket_weights(&legs, &values, amount) {
+            set_reentrancy_guard(&e, false);
+            fail(&e, err, "create_basket_commitment");
+        }
+        for leg in legs.iter() {
+            if let Err(err) =
+                check_asset_limits(&e, &owner, &leg.asset_address, leg.amount, leg.amount)
+            {
+                set_reentrancy_guard(&e, false);
+                fail(&e, err, "create_basket_commitment");
+            }
+            check_sufficient_balance(&e, &owner, &leg.asset_address, leg.amount);
+        }
+
+        let (current_total, current_tvl, nft_contract) = {
+            let total = e
+                .storage()
+                .instance()
+                .get::<_, u64>(&DataKey::TotalCommitments)
+                .unwrap_or(0);
+            let tvl = e
+                .storage()
+                .instance()
+                .get::<_, i128>(&DataKey::TotalValueLocked)
+                .unwrap_or(0);
+            let nft = e
+                .storage()
+                .instance()
+                .get::<_, Address>(&DataKey::NftContract)
+                .unwrap_or_else(|| {
+                    set_reentrancy_guard(&e, false);
+                    fail(
+                        &e,
+                        CommitmentError::NotInitialized,
+                        "create_basket_commitment",
+                    )
+                });
+            (total, tvl, nft)
+        };
+
+        let commitment_id = Self::generate_commitment_id(&e, current_total);
+        if has_commitment(&e, &commitment_id) {
+            set_reentrancy_guard(&e, false);
+            fail(
+                &e,
+                CommitmentError::InvalidStatus,
+                "create_basket_commitment",
+            );
+        }
+
+        // EFFECTS: Update state before external calls
+        let commitment = Commitment {
+            commitment_id: commitment_id.clone(),
+            owner: owner.clone(),
+            nft_token_id: 0, // Will be set after NFT mint
+            rules: rules.clone(),
+            amount,
+            asset_address: quote_asset.clone(),
+            created_at: TimeUtils::now(&e),
+            expires_at: TimeUtils::calculate_expiration(&e, rules.duration_days),
+            current_value: amount,
+            status: CommitmentStatus::Active,
+        };
+        // Legs first, so the basket is kept out of the quote asset's penalty pool
+        write_persistent(&e, &DataKey::BasketLegs(commitment_id.clone()), &legs);
+        set_commitment(&e, &commitment);
+        index_commitment(&e, &commitment);
+
+        let mut owner_commitments = read_owner_commitments(&e, &owner);
+        owner_commitments.push_back(commitment_id.clone());
+        set_owner_commitments(&e, &owner, &owner_commitments);
+
+        e.storage()
+            .instance()
+            .set(&DataKey::TotalCommitments, &(current_total + 1));
+        e.storage()
+            .instance()
+            .set(&DataKey::TotalValueLocked, &(current_tvl + amount));
+        for leg in legs.iter() {
+            adjust_asset_value_locked(&e, &owner, &leg.asset_address, leg.amount);
+        }
+
+        // INTERACTIONS: External calls (token transfers, NFT mint)
+        let contract_address = e.current_contract_address();
+        for leg in legs.iter() {
+            transfer_assets(
+                &e,
+                &owner,
+                &contract_address,
+                &leg.asset_address,
+                leg.amount,
+            );
+        }
+
+        let nft_token_id = call_nft_mint(
+            &e,
+            &nft_contract,
+            &owner,
+            &commitment_id,
+            rules.duration_days,
+            rules.max_loss_percent,
+            rules.commitment_type,
+            amount,
+            &quote_asset,
+        );
+        call_nft_set_basket(&e, &nft_contract, nft_token_id, &legs);
+
+        let mut updated_commitment = commitment;
+        updated_commitment.nft_token_id = nft_token_id;
+        set_commitment(&e, &updated_commitment);
+        set_price_mark(&e, &commitment_id, &quote_price);
+
+        // Clear reentrancy guard
+        set_reentrancy_guard(&e, false);
+
+        e.events().publish(
+            (
+                symbol_short!("Created"),
+                commitment_id.clone(),
+                owner.clone(),
+            ),
+            (amount, rules, nft_token_id, e.ledger().timestamp()),
+        );
+        e.events().publish(
+            (symbol_short!("Basket"), commitment_id.clone()),
+            (quote_asset, legs),
+        );
+        commitment_id
+    }
+
+    /// Move a commitment to the new holder of its NFT.
+    ///
+    /// Called by the NFT contract from `transfer`, so settlement payouts, early exit,
+    /// penalty share claims and the owner index follow the token. Tokens that do not back
+    /// a commitment of this contract are ignored.
+    pub fn on_nft_transfer(
+        e: Env,
+        commitment_id: String,
+        token_id: u32,
+        from: Address,
+        to: Address,
+    ) {
+        let nft_contract = e
+            .storage()
+            .instance()
+            .get::<_, Address>(&DataKey::NftContract)
+            .unwrap_or_else(|| fail(&e, CommitmentError::NotInitialized, "on_nft_transfer"));
+        nft_contract.require_auth();
+
+        let mut commitment = match read_commitment(&e, &commitment_id) {
+            Some(commitment) if commitment.nft_token_id == token_id => commitment,
+            _ => return,
+        };
+        if commitment.owner != from {
+            fail(&e, CommitmentError::Unauthorized, "on_nft_transfer");
+        }
+
+        let mut from_ids = read_owner_commitments(&e, &from);
+        if let Some(index) = from_ids.first_index_of(&commitment_id) {
+            from_ids.remove(index);
+        }
+        set_owner_commitments(&e, &from, &from_ids);
+        let mut to_ids = read_owner_commitments(&e, &to);
+        to_ids.push_back(commitment_id.clone());
+        set_owner_commitments(&e, &to, &to_ids);
+
+        // The position's locked value now counts towards the new holder's caps
+        if commitment.status.is_open() {
+            match read_basket_legs(&e, &commitment_id) {
+                Some(legs) => {
+                    for leg in legs.iter() {
+                        adjust_asset_value_locked(&e, &from, &leg.asset_address, -leg.amount);
+                        adjust_asset_value_locked(&e, &to, &leg.asset_address, leg.amount);
+                    }
+                }
+                None => {
+                    let value = commitment.current_value;
+                    adjust_asset_value_locked(&e, &from, &commitment.asset_address, -value);
+                    adjust_asset_value_locked(&e, &to, &commitment.asset_address, value);
+                }
+            }
+        }
+
+        commitment.owner = to.clone();
+        set_commitment(&e, &commitment);
+
+        e.events().publish(
+            (symbol_short!("OwnerXfer"), commitment_id),
+            (from, to, e.ledger().timestamp()),
+        );
+    }
+
+    /// List commitments matching `filter`, one page at a time.
+    ///
+    /// Candidates are read from the narrowest source the filter allows: the owner's
+    /// commitment list, then the status index (when filtering on a single status), then
+    /// the asset index (which only holds open commitments, so it is used when the filter
+    /// is limited to open statuses), otherwise every commitment in creation order. Until
+    /// `migrate_storage` has built the indexes, status and asset filters fall back to the
+    /// full scan. `cursor` is a position in that source
+    /// (0 for the first page); pass the returned `next_cursor` to continue.
+    ///
+    /// At most `MAX_PAGE_SCAN` candidates are examined per call, so a page can hold fewer
+    /// than `limit` results while `next_cursor` is still set. Status index order changes
+    /// as commitments change status, so a listing may skip or repeat moving entries.
+    pub fn list_commitments(
+        e: Env,
+        cursor: u64,
+        limit: u32,
+        filter: CommitmentFilter,
+    ) -> CommitmentPage {
+        if limit == 0 {
+            fail(&e, CommitmentError::InvalidAmount, "list_commitments");
+        }
+        let limit = core::cmp::min(limit, MAX_PAGE_SIZE);
+
+        let owner_ids = filter
+            .owner
+            .as_ref()
+            .map(|owner| read_owner_commitments(&e, owner));
+        let index = if owner_ids.is_some() || read_storage_version(&e) < STORAGE_VERSION {
+            None
+        } else if filter.statuses.len() == 1 {
+            filter.statuses.first().map(CommitmentIndex::Status)
+        } else if !filter.statuses.is_empty()
+            && filter.statuses.iter().all(|status| status.is_open())
+        {
+            filter.asset.clone().map(CommitmentIndex::Asset)
+        } else {
+            None
+        };
+        let total = match (&owner_ids, &index) {
+            (Some(commitment_ids), _) => commitment_ids.len() as u64,
+            (None, Some(index)) => index_len(&e, index),
+            (None, None) => Self::get_total_commitments(e.clone()),
+        };
+
+        let scan_end = core::cmp::min(total, cursor.saturating_add(MAX_PAGE_SCAN));
+        let mut position = cursor;
+        let mut commitments = Vec::new(&e);
+        while position < scan_end && commitments.len() < limit {
+            let commitment_id = match (&owner_ids, &index) {
+                (Some(commitment_ids), _) => commitment_ids.get(position as u32),
+                (None, Some(index)) => index_get(&e, index, position),
+                (None, None) => Some(Self::generate_commitment_id(&e, position)),
+            };
+            position += 1;
+
+            if let Some(commitment) = commitment_id.and_then(|id| read_commitment(&e, &id)) {
+                if filter.matches(&commitment) {
+                    commitments.push_back(commitment);
+                }
+            }
+        }
+
+        CommitmentPage {
+            commitments,
+            next_cursor: if position < total {
+                Some(position)
+            } else {
+                None
+            },
+        }
+    }
+
+    /// Get the units of `asset` locked in open commitments, counting each basket leg
+    /// under its own asset.
+    pub fn get_tvl_by_asset(e: Env, asset: Address) -> i128 {
+        read_asset_value_locked(&e, &asset)
+    }
+
+    /// Get the units locked for every asset in the registry, in listing order.
+    pub fn get_tvl_breakdown(e: Env) -> Vec<(Address, i128)> {
+        let mut breakdown = Vec::new(&e);
+        for asset in read_listed_assets(&e).iter() {
+            let locked = read_asset_value_locked(&e, &asset);
+            breakdown.push_back((asset, locked));
+        }
+        breakdown
+    }
+
+    /// Get the legs of a basket commitment; empty for single-asset commitments.
+    pub fn get_basket(e: Env, commitment_id: String) -> Vec<BasketLeg> {
+        read_basket_legs(&e, &commitment_id).unwrap_or_else(|| Vec::new(&e))
+    }
+
+    /// Get admin address
+    pub fn get_admin(e: Env) -> Address {
+        e.storage()
+            .instance()
+            .get::<_, Address>(&DataKey::Admin)
+            .unwrap_or_else(|| fail(&e, CommitmentError::NotInitialized, "get_admin"))
+    }
+
+    /// Get NFT contract address
+    pub fn get_nft_contract(e: Env) -> Address {
+        e.storage()
+            .instance()
+            .get::<_, Address>(&DataKey::NftContract)
+            .unwrap_or_else(|| fail(&e, CommitmentError::NotInitialized, "get_nft_contract"))
+    }
+
+    /// Set allocation contract address (only admin can call)
+    pub fn set_allocation_contract(e: Env, caller: Address, allocation_contract: Address) {
+        require_admin(&e, &caller);
+        e.storage()
+            .instance()
+            .set(&DataKey::AllocationContract, &allocation_contract);
+    }
+
+    /// Get allocation contract address
+    pub fn get_allocation_contract(e: Env) -> Option<Address> {
+        e.storage()
+            .instance()
+            .get::<_, Address>(&DataKey::AllocationContract)
+    }
+
+    /// Update commitment value (called by allocation logic or oracle-fed keeper).
+    /// Persists new_value to commitment.current_value and updates TotalValueLocked.
+    /// 
+    /// # Access Control
+    /// Only the admin or authorized allocation contract can call this function.
+    pub fn update_value(e: Env, caller: Address, commitment_id: String, new_value: i128) {
+        // Access control: only admin or authorized allocation contract can update value
+        let admin = e
+            .storage()
+            .instance()
+            .get::<_, Address>(&DataKey::Admin)
+            .unwrap_or_else(|| fail(&e, CommitmentError::NotInitialized, "update_value"));
+        
+        let allocation_contract = e
+            .storage()
+            .instance()
+            .get::<_, Address>(&DataKey::AllocationContract);
+        
+        let is_authorized = caller == admin || 
+            (allocation_contract.is_some() && caller == allocation_contract.unwrap());
+        
+        if !is_authorized {
+            fail(&e, CommitmentError::Unauthorized, "update_value");
+        }
+
+        // Global per-function rate limit (per contract instance)
+        let fn_symbol = symbol_short!("upd_val");
+        let contract_address = e.current_contract_address();
+        RateLimiter::check(&e, &contract_address, &fn_symbol);
+
+        Validation::require_non_negative(new_value);
+
+        let commitment = read_commitment(&e, &commitment_id)
+            .unwrap_or_else(|| fail(&e, CommitmentError::CommitmentNotFound, "update_value"));
+
+        if !commitment.status.is_open() {
+            fail(&e, CommitmentError::NotActive, "update_value");
+        }
+
+        // A marked single-asset commitment is loss-checked at today's oracle price when
+        // one is available; otherwise the reported value counts at par
+        let valuation = match oracle_valuation(&e, &commitment, new_value) {
+            Some(valuation) => {
+                write_marked_value(&e, &commitment_id, valuation);
+                valuation
+            }
+            None => new_value,
+        };
+        apply_value_update(&e, commitment, new_value, valuation);
+    }
+
+    /// Mark a commitment to market using the configured price oracle.
+    ///
+    /// Reads `get_price_valid` for the commitment's `asset_address`, values `current_value`
+    /// at the price move since its mark and runs the same loss/grace/violation logic as
+    /// `update_value` on that valuation. The valuation is stored apart (see
+    /// `get_marked_value`); `current_value` stays the token balance that is paid out. The
+    /// first valuation of a commitment without a mark only records the reference price.
+    /// Baskets are revalued from their legs into the quote asset, which is their value.
+    /// Permissionless: the price comes from the oracle, not the caller, who is only
+    /// identified for the per-caller rate limit.
+    ///
+    /// # Errors
+    /// - `OracleNotConfigured` if no price oracle is set
+    /// - `StalePrice` if the oracle price is older than its staleness window
+    /// - `PriceUnavailable` if the oracle has no usable price for the asset
+    pub fn revalue(e: Env, commitment_id: String, caller: Address) -> i128 {
+        // Rate limit per caller so no one can use up revaluation for everyone else
+        caller.require_auth();
+        let fn_symbol = symbol_short!("revalue");
+        RateLimiter::check(&e, &caller, &fn_symbol);
+
+        let commitment = read_commitment(&e, &commitment_id)
+            .unwrap_or_else(|| fail(&e, CommitmentError::CommitmentNotFound, "revalue"));
+
+        if !commitment.status.is_open() {
+            fail(&e, CommitmentError::NotActive, "revalue");
+        }
+
+        let price_oracle = e
+            .storage()
+            .instance()
+            .get::<_, Address>(&DataKey::PriceOracle)
+            .unwrap_or_else(|| fail(&e, CommitmentError::OracleNotConfigured, "revalue"));
+        let price = read_oracle_price(&e, &price_oracle, &commitment.asset_address)
+            .unwrap_or_else(|err| fail(&e, err, "revalue"));
+
+        // Baskets are valued from scratch: each leg converted at its own price into the quote
+        // asset (the commitment's `asset_address`)
+        if let Some(legs) = read_basket_legs(&e, &commitment_id) {
+            set_price_mark(&e, &commitment_id, &price);
+            let old_value = commitment.current_value;
+            let new_value = basket_leg_values(&e, &price_oracle, &price, &legs)
+                .unwrap_or_else(|err| fail(&e, err, "revalue"))
+                .iter()
+                .fold(0i128, SafeMath::add);
+            e.events().publish(
+                (symbol_short!("Revalued"), commitment_id),
+                (price.price, price.decimals, old_value, new_value),
+            );
+            apply_value_update(&e, commitment, new_value, new_value);
+            return new_value;
+        }
+
+        let mark = read_price_mark(&e, &commitment_id);
+        if mark.is_none() {
+            set_price_mark(&e, &commitment_id, &price);
+        }
+        let old_valuation = read_marked_value(&e, &commitment);
+        let valuation = marked_value(commitment.current_value, mark.as_ref(), &price);
+        write_marked_value(&e, &commitment_id, valuation);
+
+        e.events().publish(
+            (symbol_short!("Revalued"), commitment_id),
+            (price.price, price.decimals, old_valuation, valuation),
+        );
+        apply_valuation(&e, commitment, valuation);
+        valuation
+    }
+
+    /// Get a commitment's last oracle valuation (see `revalue`), or its `current_value`
+    /// if it has never been valued.
+    pub fn get_marked_value(e: Env, commitment_id: String) -> i128 {
+        let commitment = read_commitment(&e, &commitment_id)
+            .unwrap_or_else(|| fail(&e, CommitmentError::CommitmentNotFound, "get_marked_value"));
+        read_marked_value(&e, &commitment)
+    }
+
+    /// Get the oracle price a commitment was last marked at, if any.
+    pub fn get_price_mark(e: Env, commitment_id: String) -> Option<PriceMark> {
+        read_persistent::<PriceMark>(&e, &DataKey::PriceMark(commitment_id))
+    }
+
+    /// Set the price oracle used by `revalue` (admin only).
+    pub fn set_price_oracle(e: Env, caller: Address, price_oracle: Address) {
+        require_admin(&e, &caller);
+        e.storage()
+            .instance()
+            .set(&DataKey::PriceOracle, &price_oracle);
+    }
+
+    /// Get the price oracle used by `revalue`, if configured.
+    pub fn get_price_oracle(e: Env) -> Option<Address> {
+        e.storage()
+            .instance()
+            .get::<_, Address>(&DataKey::PriceOracle)
+    }
+
+    /// Get detailed violation information
+    /// Returns a tuple: (has_violations, loss_violated, duration_violated, loss_percent, time_remaining, grace_deadline)
+    ///
+    /// `grace_deadline` is the timestamp at which an `AtRisk` commitment becomes violated
+    /// if it has not recovered, or 0 when no grace window is running. A loss breach only
+    /// sets `loss_violated` once that deadline has passed.
+    pub fn get_violation_details(
+        e: Env,
+        commitment_id: String,
+    ) -> (bool, bool, bool, i128, u64, u64) {
+        let commitment = read_commitment(&e, &commitment_id).unwrap_or_else(|| {
+            fail(
+                &e,
+                CommitmentError::CommitmentNotFound,
+                "get_violation_details",
+            )
+        });
+
+        let current_time = e.ledger().timestamp();
+
+        // Calculate loss percentage
+        let loss_amount = commitment.amount - commitment.current_value;
+        let loss_percent = if commitment.amount > 0 {
+            (loss_amount * 100) / commitment.amount
+        } else {
+            0
+        };
+
+        // Check loss limit violation
+        let max_loss = commitment.rules.max_loss_percent as i128;
+        let loss_violated = loss_violation_confirmed(&e, &commitment, loss_percent > max_loss);
+
+        // Check duration violation
+        let duration_violated = current_time >= commitment.expires_at;
+
+        // Calculate time remaining (0 if expired)
+        let time_remaining = if current_time < commitment.expires_at {
+            commitment.expires_at - current_time
+        } else {
+            0
+        };
+
+        let grace_deadline = read_grace_deadline(&e, &commitment_id).unwrap_or(0);
+
+        let has_violations = loss_violated || duration_violated;
+
+        (
+            has_violations,
+            loss_violated,
+            duration_violated,
+            loss_percent,
+            time_remaining,
+            grace_deadline,
+        )
+    }
+
+    /// Finalize an `AtRisk` commitment whose grace window has ended without recovery.
+    ///
+    /// Permissionless so keepers can enforce the deadline without waiting for the next
+    /// value update. Returns `true` if the commitment was moved to `Violated`.
+    pub fn enforce_grace_period(e: Env, commitment_id: String) -> bool {
+        let mut commitment = read_commitment(&e, &commitment_id).unwrap_or_else(|| {
+            fail(
+                &e,
+                CommitmentError::CommitmentNotFound,
+                "enforce_grace_period",
+            )
+        });
+
+        if commitment.status != CommitmentStatus::AtRisk {
+            return false;
+        }
+
+        let deadline = read_grace_deadline(&e, &commitment_id).unwrap_or(0);
+        if e.ledger().timestamp() < deadline {
+            return false;
+        }
+
+        set_status(&e, &mut commitment, CommitmentStatus::Violated);
+        set_commitment(&e, &commitment);
+        remove_grace_deadline(&e, &commitment_id);
+
+        let loss_percent = if commitment.amount > 0 {
+            SafeMath::loss_percent(commitment.amount, commitment.current_value)
+        } else {
+            0
+        };
+        e.events().publish(
+            (symbol_short!("Violated"), commitment_id),
+            (
+                loss_percent,
+                commitment.rules.max_loss_percent,
+                e.ledger().timestamp(),
+            ),
+        );
+        true
+    }
+
+    /// Settle commitment at maturity
+    ///
+    /// Anyone may settle, so a commitment with `auto_rollover` restarts for another term
+    /// whoever calls this, exactly as under `batch_settle`, but only once
+    /// `ROLLOVER_OWNER_WINDOW` has passed since expiry. Until then the owner decides: opt
+    /// out with `set_auto_rollover` or `claim_vested` to be paid out, or `renew`.
+    ///
+    /// # Reentrancy Protection
+    /// Uses checks-effects-interactions pattern with reentrancy guard.
+    pub fn settle(e: Env, commitment_id: String) {
+        // Reentrancy protection
+        require_no_reentrancy(&e);
+        set_reentrancy_guard(&e, true);
+
+        // Check if contract is paused
+        Pausable::require_not_paused(&e);
+
+        if let Err(err) = settle_commitment(&e, &commitment_id, None, true) {
+            set_reentrancy_guard(&e, false);
+            fail(&e, err, "settle");
+        }
+
+        // Clear reentrancy guard
+        set_reentrancy_guard(&e, false);
+    }
+
+    /// Settle expired commitments in one call, paying `keeper` a bounty from each.
+    ///
+    /// Ids that cannot be settled (unknown, not expired, already closed, a rollover still
+    /// in its `ROLLOVER_OWNER_WINDOW`, or a rollover whose NFT renewal fails) are skipped and reported in the result's `errors` with
+    /// their index, the `CommitmentError` code and the commitment id. The bounty is `KeeperBountyBps` of each settlement amount, paid
+    /// in the commitment's asset; nothing is paid for a keeper's own commitments.
+    pub fn batch_settle(e: Env, keeper: Address, commitment_ids: Vec<String>) -> BatchResultVoid {
+        // Reentrancy protection
+        require_no_reentrancy(&e);
+        Pausable::require_not_paused(&e);
+        keeper.require_auth();
+
+        // Validate batch size
+        let batch_size = commitment_ids.len();
+        let contract_name = String::from_str(&e, "commitment_core");
+        if let Err(error_code) =
+            BatchProcessor::enforce_batch_limits(&e, batch_size, Some(contract_name))
+        {
+            let mut errors = Vec::new(&e);
+            errors.push_back(BatchError {
+                index: 0,
+                error_code,
+                context: String::from_str(&e, "batch_size_validation"),
+            });
+            return BatchResultVoid::failure(&e, errors);
+        }
+
+        set_reentrancy_guard(&e, true);
+        let mut settled: u32 = 0;
+        let mut errors = Vec::new(&e);
+        for (index, commitment_id) in commitment_ids.iter().enumerate() {
+            match settle_commitment(&e, &commitment_id, Some(&keeper), true) {
+                Ok(_) => settled += 1,
+                Err(err) => errors.push_back(BatchError {
+                    index: index as u32,
+                    error_code: err as u32,
+                    context: commitment_id,
+                }),
+            }
+        }
+        set_reentrancy_guard(&e, false);
+
+        e.events().publish(
+            (symbol_short!("BatchSetl"), keeper),
+            (settled, errors.len(), e.ledger().timestamp()),
+        );
+        BatchResultVoid::partial(settled, errors)
+    }
+
+    /// Renew an expired commitment for another term under `new_rules`.
+    ///
+    /// `caller` must be the owner or an operator approved for `OperatorScope::Renew`.
+    ///
+    /// The commitment keeps its id, NFT and locked funds: instead of paying out, the term
+    /// restarts now with `current_value` as the new principal, and the NFT is re-stamped
+    /// with the new `expires_at`. The commitment type cannot change on renewal.
+    ///
+    /// # Reentrancy Protection
+    /// Uses checks-effects-interactions pattern with reentrancy guard.
+    pub fn renew(e: Env, commitment_id: String, caller: Address, new_rules: CommitmentRules) {
+        // Reentrancy protection
+        require_no_reentrancy(&e);
+        set_reentrancy_guard(&e, true);
+
+        // Check if contract is paused
+        Pausable::require_not_paused(&e);
+        EmergencyControl::require_not_emergency(&e);
+
+        // CHECKS: Get and validate commitment
+        let mut commitment = read_commitment(&e, &commitment_id).unwrap_or_else(|| {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::CommitmentNotFound, "renew")
+        });
+        caller.require_auth();
+        if commitment.owner != caller
+            && !is_operator_for(&e, &commitment, &caller, OperatorScope::Renew)
+        {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::Unauthorized, "renew");
+        }
+
+        if !commitment.status.is_open() {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::NotActive, "renew");
+        }
+        if e.ledger().timestamp() < commitment.expires_at {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::NotExpired, "renew");
+        }
+        if commitment.current_value <= 0 {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::InvalidAmount, "renew");
+        }
+
+        Self::validate_rules(&new_rules);
+        validate_penalty_schedule(&e, &new_rules);
+        validate_release_schedule(&e, &new_rules);
+        if new_rules.commitment_type != commitment.rules.commitment_type {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::InvalidCommitmentType, "renew");
+        }
+        if new_rules.release_schedule != ReleaseSchedule::AtExpiry
+            && read_basket_legs(&e, &commitment_id).is_some()
+        {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::BasketNotSupported, "renew");
+        }
+
+        let nft_contract = e
+            .storage()
+            .instance()
+            .get::<_, Address>(&DataKey::NftContract)
+            .unwrap_or_else(|| {
+                set_reentrancy_guard(&e, false);
+                fail(&e, CommitmentError::NotInitialized, "renew")
+            });
+
+        if let Err(err) = renew_commitment(&e, &mut commitment, new_rules, &nft_contract) {
+            set_reentrancy_guard(&e, false);
+            fail(&e, err, "renew");
+        }
+
+        // Clear reentrancy guard
+        set_reentrancy_guard(&e, false);
+    }
+
+    /// Opt a commitment in or out of auto-rollover at settlement (owner only).
+    pub fn set_auto_rollover(e: Env, commitment_id: String, enabled: bool) {
+        Pausable::require_not_paused(&e);
+
+        let mut commitment = read_commitment(&e, &commitment_id)
+            .unwrap_or_else(|| fail(&e, CommitmentError::CommitmentNotFound, "set_auto_rollover"));
+        commitment.owner.require_auth();
+        if !commitment.status.is_open() {
+            fail(&e, CommitmentError::NotActive, "set_auto_rollover");
+        }
+
+        commitment.rules.auto_rollover = enabled;
+        validate_release_schedule(&e, &commitment.rules);
+        set_commitment(&e, &commitment);
+
+        e.events().publish(
+            (symbol_short!("AutoRoll"), commitment_id),
+            (enabled, e.ledger().timestamp()),
+        );
+    }
+
+    /// Set the keeper bounty paid by `batch_settle`, in basis points of each settlement
+    /// amount (only admin can call)
+    pub fn set_keeper_bounty_bps(e: Env, caller: Address, bps: u32) {
+        require_admin(&e, &caller);
+        if bps > BPS_MAX {
+            fail(&e, CommitmentError::InvalidFeeBps, "set_keeper_bounty_bps");
+        }
+        e.storage().instance().set(&DataKey::KeeperBountyBps, &bps);
+    }
+
+    /// Get the keeper bounty in basis points (defaults to 0)
+    pub fn get_keeper_bounty_bps(e: Env) -> u32 {
+        read_keeper_bounty_bps(&e)
+    }
+
+    /// Check attested fee generation against `min_fee_threshold` (keeper entry point).
+    ///
+    /// Reads `fees_generated` from the configured attestation engine and records the
+    /// result, which `get_fee_check` exposes. Consequences are only applied at settlement,
+    /// so checking before expiry just flags a commitment that is behind.
+    /// Returns `true` if the threshold is met or no threshold applies.
+    pub fn check_fee_threshold(e: Env, commitment_id: String) -> bool {
+        let commitment = read_commitment(&e, &commitment_id).unwrap_or_else(|| {
+            fail(
+                &e,
+                CommitmentError::CommitmentNotFound,
+                "check_fee_threshold",
+            )
+        });
+
+        if !commitment.status.is_open() {
+            fail(&e, CommitmentError::NotActive, "check_fee_threshold");
+        }
+
+        match run_fee_check(&e, &commitment) {
+            Some(check) => check.shortfall == 0,
+            None => true,
+        }
+    }
+
+    /// Get the latest recorded fee threshold check for a commitment.
+    pub fn get_fee_check(e: Env, commitment_id: String) -> Option<FeeCheck> {
+        read_persistent::<FeeCheck>(&e, &DataKey::FeeCheck(commitment_id))
+    }
+
+    /// Set the attestation engine used for fee threshold checks (only admin can call)
+    pub fn set_attestation_engine(e: Env, caller: Address, attestation_engine: Address) {
+        require_admin(&e, &caller);
+        e.storage()
+            .instance()
+            .set(&DataKey::AttestationEngine, &attestation_engine);
+    }
+
+    /// Get attestation engine address
+    pub fn get_attestation_engine(e: Env) -> Option<Address> {
+        e.storage()
+            .instance()
+            .get::<_, Address>(&DataKey::AttestationEngine)
+    }
+
+    /// Set the consequence applied at settlement on a fee shortfall (only admin can call)
+    pub fn set_fee_shortfall_action(e: Env, caller: Address, action: FeeShortfallAction) {
+        require_admin(&e, &caller);
+        e.storage()
+            .instance()
+            .set(&DataKey::FeeShortfallAction, &action);
+    }
+
+    /// Get the fee shortfall consequence (defaults to `Flag`)
+    pub fn get_fee_shortfall_action(e: Env) -> FeeShortfallAction {
+        e.storage()
+            .instance()
+            .get::<_, FeeShortfallAction>(&DataKey::FeeShortfallAction)
+            .unwrap_or(FeeShortfallAction::Flag)
+    }
+
+    /// Quote an early exit at the current ledger time.
+    ///
+    /// Returns `(penalty, net_amount)` for exiting the whole position now, following the
+    /// commitment's penalty schedule and floor. Fails if the commitment is not open.
+    pub fn quote_early_exit(e: Env, commitment_id: String) -> (i128, i128) {
+        let commitment = read_commitment(&e, &commitment_id)
+            .unwrap_or_else(|| fail(&e, CommitmentError::CommitmentNotFound, "quote_early_exit"));
+        if !commitment.status.is_open() {
+            fail(&e, CommitmentError::NotActive, "quote_early_exit");
+        }
+        let penalty = early_exit_penalty_amount(&e, &commitment, commitment.current_value);
+        (penalty, SafeMath::sub(commitment.current_value, penalty))
+    }
+
+    pub fn early_exit(e: Env, commitment_id: String, caller: Address) {
+        // Reentrancy protection
+        require_no_reentrancy(&e);
+        set_reentrancy_guard(&e, true);
+
+        // Check if contract is paused
+        Pausable::require_not_paused(&e);
+
+        // CHECKS: Get and validate commitment
+        let mut commitment = read_commitment(&e, &commitment_id).unwrap_or_else(|| {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::CommitmentNotFound, "early_exit")
+        });
+
+        // Verify caller is the owner or an approved operator
+        caller.require_auth();
+        if commitment.owner != caller
+            && !is_operator_for(&e, &commitment, &caller, OperatorScope::EarlyExit)
+        {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::Unauthorized, "early_exit");
+        }
+
+        // Verify commitment is open (an AtRisk commitment is still live)
+        if !commitment.status.is_open() {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::NotActive, "early_exit");
+        }
+        if active_clawback_funder(&e, &commitment_id).is_some() {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::ClawbackActive, "early_exit");
+        }
+
+        // Recall allocated funds so the exit pays out in full
+        if unwind_allocations(&e, &commitment) > 0 {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::AllocationNotReturned, "early_exit");
+        }
+
+        // EFFECTS: Calculate penalty from the commitment's penalty schedule
+        let exit_value = commitment.current_value;
+        let penalty_amount = early_exit_penalty_amount(&e, &commitment, exit_value);
+        let returned_amount = SafeMath::sub(exit_value, penalty_amount);
+
+        // Update commitment status to early_exit
+        set_status(&e, &mut commitment, CommitmentStatus::EarlyExit);
+        commitment.current_value = 0; // All value has been distributed
+        set_commitment(&e, &commitment);
+        remove_grace_deadline(&e, &commitment_id);
+
+        // (asset, returned amount, burn amount); each basket leg is charged the same
+        // penalty in its own asset
+        let mut payouts = Vec::new(&e);
+        match read_basket_legs(&e, &commitment_id) {
+            Some(legs) => {
+                for leg in legs.iter() {
+                    let leg_penalty = early_exit_penalty_amount(&e, &commitment, leg.amount);
+                    let burn = route_penalty(&e, &commitment, &leg.asset_address, leg_penalty);
+                    adjust_asset_value_locked(
+                        &e,
+                        &commitment.owner,
+                        &leg.asset_address,
+                        -leg.amount,
+                    );
+                    payouts.push_back((
+                        leg.asset_address.clone(),
+                        SafeMath::sub(leg.amount, leg_penalty),
+                        burn,
+                    ));
+                }
+            }
+            None => {
+                let burn =
+                    route_penalty(&e, &commitment, &commitment.asset_address, penalty_amount);
+                adjust_asset_value_locked(
+                    &e,
+                    &commitment.owner,
+                    &commitment.asset_address,
+                    -exit_value,
+                );
+                payouts.push_back((commitment.asset_address.clone(), returned_amount, burn));
+            }
+        }
+
+        // Decrease total value locked by full current value (no longer locked)
+        let current_tvl = e
+            .storage()
+            .instance()
+            .get::<_, i128>(&DataKey::TotalValueLocked)
+            .unwrap_or(0);
+        let new_tvl = current_tvl - exit_value;
+        e.storage()
+            .instance()
+            .set(&DataKey::TotalValueLocked, &new_tvl);
+
+        // INTERACTIONS: External calls (token transfer)
+        // Transfer remaining amount (after penalty) to owner
+        let contract_address = e.current_contract_address();
+        for (asset, returned, burn) in payouts.iter() {
+            let token_client = token::Client::new(&e, &asset);
+            if returned > 0 {
+                token_client.transfer(&contract_address, &commitment.owner, &returned);
+            }
+            if burn > 0 {
+                token_client.burn(&contract_address, &burn);
+            }
+        }
+
+        // Call NFT contract to update NFT status (mark as inactive/early_exited)
+        let nft_contract = e
+            .storage()
+            .instance()
+            .get::<_, Address>(&DataKey::NftContract)
+            .unwrap_or_else(|| {
+                set_reentrancy_guard(&e, false);
+                fail(&e, CommitmentError::NotInitialized, "early_exit")
+            });
+
+        // Call settle on NFT to mark it as inactive (pass self as caller for access control)
+        let core_address = e.current_contract_address();
+        let mut args = Vec::new(&e);
+        args.push_back(core_address.into_val(&e));
+        args.push_back(commitment.nft_token_id.into_val(&e));
+        e.invoke_contract::<()>(&nft_contract, &Symbol::new(&e, "settle"), args);
+
+        // Clear reentrancy guard
+        set_reentrancy_guard(&e, false);
+
+        // Emit early exit event with detailed information
+        e.events().publish(
+            (
+                symbol_short!("EarlyExt"),
+                commitment_id.clone(),
+                caller.clone(),
+            ),
+            (penalty_amount, returned_amount, e.ledger().timestamp()),
+        );
+    }
+
+    /// Set where early-exit penalties are sent (only admin can call)
+    pub fn set_penalty_destination(e: Env, caller: Address, destination: PenaltyDestination) {
+        require_admin(&e, &caller);
+        e.storage()
+            .instance()
+            .set(&DataKey::PenaltyDestination, &destination);
+    }
+
+    /// Get the early-exit penalty destination (defaults to `Treasury`)
+    pub fn get_penalty_destination(e: Env) -> PenaltyDestination {
+        read_penalty_destination(&e)
+    }
+
+    /// Set the address that claims treasury penalties (only admin can call)
+    pub fn set_penalty_treasury(e: Env, caller: Address, treasury: Address) {
+        require_admin(&e, &caller);
+        e.storage()
+            .instance()
+            .set(&DataKey::PenaltyTreasury, &treasury);
+    }
+
+    /// Get the penalty treasury address
+    pub fn get_penalty_treasury(e: Env) -> Option<Address> {
+        e.storage()
+            .instance()
+            .get::<_, Address>(&DataKey::PenaltyTreasury)
+    }
+
+    /// Add or update an asset in the registry (only admin can call).
+    ///
+    /// New commitments and top-ups in the asset must then respect `config`; existing
+    /// positions are not affected. Set `enabled: false` to stop new deposits.
+    pub fn set_asset_config(e: Env, caller: Address, asset: Address, config: AssetConfig) {
+        require_admin(&e, &caller);
+        if config.min_amount < 0
+            || config.max_amount < 0
+            || (config.max_amount > 0 && config.max_amount < config.min_amount)
+            || config.global_cap < 0
+            || config.owner_cap < 0
+        {
+            fail(&e, CommitmentError::InvalidAmount, "set_asset_config");
+        }
+
+        let mut listed = read_listed_assets(&e);
+        if !listed.contains(&asset) {
+            listed.push_back(asset.clone());
+            e.storage().instance().set(&DataKey::ListedAssets, &listed);
+        }
+        e.storage()
+            .instance()
+            .set(&DataKey::AssetConfig(asset.clone()), &config);
+
+        e.events().publish(
+            (symbol_short!("AssetCfg"), asset),
+            (config, e.ledger().timestamp()),
+        );
+    }
+
+    /// Get an asset's registry entry, if listed
+    pub fn get_asset_config(e: Env, asset: Address) -> Option<AssetConfig> {
+        read_asset_config(&e, &asset)
+    }
+
+    /// Get all assets in the registry, in listing order
+    pub fn get_listed_assets(e: Env) -> Vec<Address> {
+        read_listed_assets(&e)
+    }
+
+    /// Require assets to be listed before they can be committed (only admin can call).
+    /// When off (the default), unlisted assets are accepted without limits.
+    pub fn set_allowlist_enforced(e: Env, caller: Address, enforced: bool) {
+        require_admin(&e, &caller);
+        e.storage()
+            .instance()
+            .set(&DataKey::AllowlistEnforced, &enforced);
+    }
+
+    /// Check whether unlisted assets are rejected
+    pub fn is_allowlist_enforced(e: Env) -> bool {
+        allowlist_enforced(&e)
+    }
+
+    /// Add or replace a catalog template (only admin can call).
+    ///
+    /// Commitments already made from the template keep the rules they were created with;
+    /// its `allocation_pool` applies to them as currently set.
+    pub fn set_template(
+        e: Env,
+        caller: Address,
+        template_id: Symbol,
+        template: CommitmentTemplate,
+    ) {
+        require_admin(&e, &caller);
+        if template.min_duration_days == 0
+            || template.min_duration_days > template.max_duration_days
+        {
+            fail(&e, CommitmentError::InvalidTemplate, "set_template");
+        }
+        Self::validate_rules(&template.rules);
+        validate_penalty_schedule(&e, &template.rules);
+        // The release schedule has to fit the shortest term the template allows
+        let mut shortest = template.rules.clone();
+        shortest.duration_days = template.min_duration_days;
+        validate_release_schedule(&e, &shortest);
+
+        let mut listed = read_listed_templates(&e);
+        if !listed.contains(&template_id) {
+            listed.push_back(template_id.clone());
+            e.storage()
+                .instance()
+                .set(&DataKey::ListedTemplates, &listed);
+        }
+        e.storage()
+            .instance()
+            .set(&DataKey::Template(template_id.clone()), &template);
+
+        e.events().publish(
+            (symbol_short!("Template"), template_id),
+            (template, e.ledger().timestamp()),
+        );
+    }
+
+    /// Get a catalog template, if listed
+    pub fn get_template(e: Env, template_id: Symbol) -> Option<CommitmentTemplate> {
+        read_template(&e, &template_id)
+    }
+
+    /// Get all catalog template ids, in listing order
+    pub fn get_listed_templates(e: Env) -> Vec<Symbol> {
+        read_listed_templates(&e)
+    }
+
+    /// Template a commitment was created from, if any
+    pub fn get_commitment_template(e: Env, commitment_id: String) -> Option<Symbol> {
+        read_commitment_template(&e, &commitment_id)
+    }
+
+    /// Require commitments to be created from templates (only admin can call). When on,
+    /// `create_commitment`, `create_commitment_for` and `create_basket_commitment` are
+    /// rejected.
+    pub fn set_templates_enforced(e: Env, caller: Address, enforced: bool) {
+        require_admin(&e, &caller);
+        e.storage()
+            .instance()
+            .set(&DataKey::TemplatesEnforced, &enforced);
+    }
+
+    /// Check whether commitments must be created from templates
+    pub fn is_templates_enforced(e: Env) -> bool {
+        templates_enforced(&e)
+    }
+
+    /// Get early-exit penalty totals for an asset
+    pub fn get_penalty_account(e: Env, asset_address: Address) -> PenaltyAccount {
+        read_penalty_account(&e, &asset_address)
+    }
+
+    /// Transfer the treasury's unclaimed penalties in `asset_address` to the treasury.
+    ///
+    /// Must be authorized by the configured treasury. Returns the amount claimed.
+    pub fn claim_penalties(e: Env, asset_address: Address) -> i128 {
+        // Reentrancy protection
+        require_no_reentrancy(&e);
+        Pausable::require_not_paused(&e);
+
+        let treasury = Self::get_penalty_treasury(e.clone())
+            .unwrap_or_else(|| fail(&e, CommitmentError::TreasuryNotSet, "claim_penalties"));
+        treasury.require_auth();
+
+        // EFFECTS
+        let mut account = read_penalty_account(&e, &asset_address);
+        let amount = account.treasury_balance;
+        if amount == 0 {
+            return 0;
+        }
+        account.treasury_balance = 0;
+        write_penalty_account(&e, &asset_address, &account);
+
+        // INTERACTIONS
+        set_reentrancy_guard(&e, true);
+        token::Client::new(&e, &asset_address).transfer(
+            &e.current_contract_address(),
+            &treasury,
+            &amount,
+        );
+        set_reentrancy_guard(&e, false);
+
+        e.events().publish(
+            (symbol_short!("PenClaim"), treasury),
+            (asset_address, amount, e.ledger().timestamp()),
+        );
+        amount
+    }
+
+    /// Redistributed penalties a commitment can currently claim
+    pub fn get_penalty_share(e: Env, commitment_id: String) -> i128 {
+        let commitment = Self::get_commitment(e.clone(), commitment_id.clone());
+        let share = read_persistent::<PenaltyShare>(&e, &DataKey::PenaltyShare(commitment_id))
+            .unwrap_or_default();
+        let pool = read_penalty_pool(
+            &e,
+            &commitment.asset_address,
+            commitment.rules.commitment_type,
+        );
+        pending_penalty_share(&share, &pool)
+    }
+
+    /// Claim the redistributed penalties credited to a commitment (owner only).
+    ///
+    /// Shares earned while the commitment was open stay claimable after it closes.
+    /// Returns the amount paid out in the commitment's asset.
+    pub fn claim_penalty_share(e: Env, commitment_id: String, owner: Address) -> i128 {
+        // Reentrancy protection
+        require_no_reentrancy(&e);
+        Pausable::require_not_paused(&e);
+
+        owner.require_auth();
+        let commitment = read_commitment(&e, &commitment_id).unwrap_or_else(|| {
+            fail(
+                &e,
+                CommitmentError::CommitmentNotFound,
+                "claim_penalty_share",
+            )
+        });
+        if commitment.owner != owner {
+            fail(&e, CommitmentError::Unauthorized, "claim_penalty_share");
+        }
+
+        // EFFECTS: bring the share up to date, then pay out everything accrued
+        sync_penalty_share(&e, &commitment);
+        let share_key = DataKey::PenaltyShare(commitment_id.clone());
+        let mut share = match read_persistent::<PenaltyShare>(&e, &share_key) {
+            Some(share) if share.accrued > 0 => share,
+            _ => return 0,
+        };
+        let amount = share.accrued;
+        share.accrued = 0;
+        write_persistent(&e, &share_key, &share);
+        let claimed_key = DataKey::PenaltySharesClaimed(commitment.asset_address.clone());
+        let claimed = read_penalty_shares_claimed(&e, &commitment.asset_address);
+        e.storage()
+            .instance()
+            .set(&claimed_key, &SafeMath::add(claimed, amount));
+
+        // INTERACTIONS
+        set_reentrancy_guard(&e, true);
+        token::Client::new(&e, &commitment.asset_address).transfer(
+            &e.current_contract_address(),
+            &owner,
+            &amount,
+        );
+        set_reentrancy_guard(&e, false);
+
+        e.events().publish(
+            (symbol_short!("ShareClm"), commitment_id),
+            (owner, amount, e.ledger().timestamp()),
+        );
+        amount
+    }
+
+    /// Add funds to an existing active commitment.
+    ///
+    /// Transfers `amount` of the commitment's `asset_address` from the owner into the
+    /// contract and increases both `amount` and `current_value`. The lock does not
+    /// restart: the original `expires_at` is kept, so topped-up funds unlock together
+    /// with the rest of the position. A commitment with an oracle mark is re-marked at
+    /// today's price so the added funds count at par; this needs a fresh price.
+    ///
+    /// # Reentrancy Protection
+    /// Uses checks-effects-interactions pattern with reentrancy guard.
+    pub fn top_up(e: Env, commitment_id: String, owner: Address, amount: i128) {
+        // Reentrancy protection
+        require_no_reentrancy(&e);
+        set_reentrancy_guard(&e, true);
+
+        // Check if contract is paused
+        Pausable::require_not_paused(&e);
+        EmergencyControl::require_not_emergency(&e);
+
+        // Rate limit: per-owner top ups
+        let fn_symbol = symbol_short!("top_up");
+        RateLimiter::check(&e, &owner, &fn_symbol);
+
+        // CHECKS: Get and validate commitment
+        let mut commitment = read_commitment(&e, &commitment_id).unwrap_or_else(|| {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::CommitmentNotFound, "top_up")
+        });
+
+        // Verify caller is owner
+        owner.require_auth();
+        if commitment.owner != owner {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::Unauthorized, "top_up");
+        }
+
+        // Verify commitment is active and still locked
+        if commitment.status != CommitmentStatus::Active {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::NotActive, "top_up");
+        }
+        if e.ledger().timestamp() >= commitment.expires_at {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::InvalidStatus, "top_up");
+        }
 
-        // Commitment type must be valid
-        let valid_types = ["safe", "balanced", "aggressive"];
-        Validation::require_valid_commitment_type(e, &rules.commitment_type, &valid_types);
-    }
+        if amount <= 0 {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::InvalidAmount, "top_up");
+        }
+        if read_basket_legs(&e, &commitment_id).is_some() {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::BasketNotSupported, "top_up");
+        }
+        let mark = read_price_mark(&e, &commitment_id);
+        let price =
+            remark_price(&e, &commitment.asset_address, mark.is_some()).unwrap_or_else(|err| {
+                set_reentrancy_guard(&e, false);
+                fail(&e, err, "top_up")
+            });
 
-    /// Generate unique commitment ID
-    /// Optimized: Uses counter to create unique ID efficiently
-    fn generate_commitment_id(e: &Env, counter: u64) -> String {
-        // OPTIMIZATION: Use counter directly as string to minimize allocations
-        // This is more gas-efficient than string concatenation
-        let mut buf = [0u8; 32];
-        let prefix = b"c_";
-        buf[0] = prefix[0];
-        buf[1] = prefix[1];
-
-        // Convert counter to string representation
-        let mut n = counter;
-        let mut i = 2;
-        if n == 0 {
-            buf[i] = b'0';
-            i += 1;
-        } else {
-            let mut digits = [0u8; 20];
-            let mut digit_count = 0;
-            while n > 0 {
-                digits[digit_count] = (n % 10) as u8 + b'0';
-                n /= 10;
-                digit_count += 1;
-            }
-            // Reverse digits
-            for j in 0..digit_count {
-                buf[i] = digits[digit_count - 1 - j];
-                i += 1;
-            }
+        if let Err(err) = check_asset_limits(
+            &e,
+            &owner,
+            &commitment.asset_address,
+            amount,
+            SafeMath::add(commitment.current_value, amount),
+        ) {
+            set_reentrancy_guard(&e, false);
+            fail(&e, err, "top_up");
         }
 
-        String::from_str(e, core::str::from_utf8(&buf[..i]).unwrap_or("c_0"))
-    }
+        check_sufficient_balance(&e, &owner, &commitment.asset_address, amount);
 
-    /// Initialize the core commitment contract
-    pub fn initialize(e: Env, admin: Address, nft_contract: Address) {
-        // Check if already initialized
-        if e.storage().instance().has(&DataKey::Admin) {
-            fail(&e, CommitmentError::AlreadyInitialized, "initialize");
+        // EFFECTS: Grow principal and value by the deposited amount
+        if let Some(price) = price {
+            let marked = SafeMath::add(
+                marked_value(commitment.current_value, mark.as_ref(), &price),
+                amount,
+            );
+            blend_price_mark(
+                &e,
+                &commitment_id,
+                SafeMath::add(commitment.current_value, amount),
+                marked,
+                &price,
+            );
         }
+        commitment.amount = SafeMath::add(commitment.amount, amount);
+        commitment.current_value = SafeMath::add(commitment.current_value, amount);
+        set_commitment(&e, &commitment);
 
-        // Store admin and NFT contract address
-        e.storage().instance().set(&DataKey::Admin, &admin);
-        e.storage()
+        let current_tvl = e
+            .storage()
             .instance()
-            .set(&DataKey::NftContract, &nft_contract);
-
-        // Initialize total commitments counter
+            .get::<_, i128>(&DataKey::TotalValueLocked)
+            .unwrap_or(0);
         e.storage()
             .instance()
-            .set(&DataKey::TotalCommitments, &0u64);
+            .set(&DataKey::TotalValueLocked, &(current_tvl + amount));
+        adjust_asset_value_locked(&e, &commitment.owner, &commitment.asset_address, amount);
 
-        // Initialize total value locked counter
-        e.storage()
+        // INTERACTIONS: External calls (token transfer, NFT metadata update)
+        let contract_address = e.current_contract_address();
+        transfer_assets(
+            &e,
+            &owner,
+            &contract_address,
+            &commitment.asset_address,
+            amount,
+        );
+
+        let nft_contract = e
+            .storage()
             .instance()
-            .set(&DataKey::TotalValueLocked, &0i128);
+            .get::<_, Address>(&DataKey::NftContract)
+            .unwrap_or_else(|| {
+                set_reentrancy_guard(&e, false);
+                fail(&e, CommitmentError::NotInitialized, "top_up")
+            });
+        call_nft_update_initial_amount(
+            &e,
+            &nft_contract,
+            commitment.nft_token_id,
+            commitment.amount,
+        );
 
-        // Initialize paused state (default: not paused)
-        e.storage().instance().set(&Pausable::PAUSED_KEY, &false);
+        // Clear reentrancy guard
+        set_reentrancy_guard(&e, false);
+
+        e.events().publish(
+            (symbol_short!("TopUp"), commitment_id, owner),
+            (
+                amount,
+                commitment.amount,
+                commitment.current_value,
+                e.ledger().timestamp(),
+            ),
+        );
     }
 
-    /// Create a new commitment
+    /// Withdraw part of an active commitment before expiry.
     ///
-    /// # Reentrancy Protection
-    /// This function uses checks-effects-interactions pattern:
-    /// 1. Checks: Validate inputs
-    /// 2. Effects: Update state (commitment storage, counters)
-    /// 3. Interactions: External calls (token transfer, NFT mint)
-    /// Reentrancy guard prevents recursive calls.
+    /// The early exit penalty is pro-rated to the withdrawn slice, and the commitment's
+    /// principal (`amount`) is reduced in the same proportion as `current_value` so the
+    /// remaining position keeps its loss percentage. The loss limit is re-checked against
+    /// the reduced basis and the withdrawal is rejected if the remainder would breach it.
+    /// Withdrawing the whole `current_value` is not allowed; use `early_exit` instead.
     ///
-    /// # Formal Verification
-    /// **Preconditions:**
-    /// - `amount > 0`
-    /// - `rules.duration_days > 0`
-    /// - `rules.max_loss_percent <= 100`
-    /// - `rules.commitment_type ∈ {"safe", "balanced", "aggressive"}`
-    /// - Contract is initialized
-    /// - `reentrancy_guard == false`
+    /// # Reentrancy Protection
+    /// Uses checks-effects-interactions pattern with reentrancy guard.
+    pub fn withdraw_partial(e: Env, commitment_id: String, caller: Address, amount: i128) {
+        // Reentrancy protection
+        require_no_reentrancy(&e);
+        set_reentrancy_guard(&e, true);
+
+        // Check if contract is paused
+        Pausable::require_not_paused(&e);
+
+        // CHECKS: Get and validate commitment
+        let mut commitment = read_commitment(&e, &commitment_id).unwrap_or_else(|| {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::CommitmentNotFound, "withdraw_partial")
+        });
+
+        // Verify caller is owner
+        caller.require_auth();
+        if commitment.owner != caller {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::Unauthorized, "withdraw_partial");
+        }
+
+        // Verify commitment is active
+        if commitment.status != CommitmentStatus::Active {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::NotActive, "withdraw_partial");
+        }
+        if active_clawback_funder(&e, &commitment_id).is_some() {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::ClawbackActive, "withdraw_partial");
+        }
+
+        // Amount must be positive and leave something locked
+        if amount <= 0 || amount >= commitment.current_value {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::InvalidAmount, "withdraw_partial");
+        }
+        if read_basket_legs(&e, &commitment_id).is_some() {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::BasketNotSupported, "withdraw_partial");
+        }
+        require_unallocated(&e, &commitment, amount, "withdraw_partial");
+
+        // Reduce the principal pro-rata to the share of current value withdrawn
+        let basis_removed = SafeMath::div(
+            SafeMath::mul(commitment.amount, amount),
+            commitment.current_value,
+        );
+        let new_basis = SafeMath::sub(commitment.amount, basis_removed);
+        let new_value = SafeMath::sub(commitment.current_value, amount);
+        if new_basis <= 0 {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::InvalidAmount, "withdraw_partial");
+        }
+
+        // Re-check the loss limit against the reduced basis
+        let loss_percent = SafeMath::loss_percent(new_basis, new_value);
+        if loss_percent > commitment.rules.max_loss_percent as i128 {
+            set_reentrancy_guard(&e, false);
+            fail(
+                &e,
+                CommitmentError::ValueUpdateViolation,
+                "withdraw_partial",
+            );
+        }
+
+        // EFFECTS: Penalty applies only to the withdrawn slice
+        let penalty_amount = early_exit_penalty_amount(&e, &commitment, amount);
+        let returned_amount = SafeMath::sub(amount, penalty_amount);
+
+        commitment.amount = new_basis;
+        commitment.current_value = new_value;
+        set_commitment(&e, &commitment);
+        let burn_amount = route_penalty(&e, &commitment, &commitment.asset_address, penalty_amount);
+
+        // The whole slice leaves the locked set (returned amount plus retained penalty)
+        let current_tvl = e
+            .storage()
+            .instance()
+            .get::<_, i128>(&DataKey::TotalValueLocked)
+            .unwrap_or(0);
+        e.storage()
+            .instance()
+            .set(&DataKey::TotalValueLocked, &(current_tvl - amount));
+        adjust_asset_value_locked(&e, &commitment.owner, &commitment.asset_address, -amount);
+
+        // INTERACTIONS: External calls (token transfer, NFT metadata update)
+        let contract_address = e.current_contract_address();
+        let token_client = token::Client::new(&e, &commitment.asset_address);
+        if returned_amount > 0 {
+            token_client.transfer(&contract_address, &commitment.owner, &returned_amount);
+        }
+        if burn_amount > 0 {
+            token_client.burn(&contract_address, &burn_amount);
+        }
+
+        let nft_contract = e
+            .storage()
+            .instance()
+            .get::<_, Address>(&DataKey::NftContract)
+            .unwrap_or_else(|| {
+                set_reentrancy_guard(&e, false);
+                fail(&e, CommitmentError::NotInitialized, "withdraw_partial")
+            });
+        call_nft_update_initial_amount(&e, &nft_contract, commitment.nft_token_id, new_basis);
+
+        // Clear reentrancy guard
+        set_reentrancy_guard(&e, false);
+
+        e.events().publish(
+            (symbol_short!("PartialWd"), commitment_id, caller),
+            (
+                amount,
+                penalty_amount,
+                returned_amount,
+                new_value,
+                e.ledger().timestamp(),
+            ),
+        );
+    }
+
+    /// Allocate liquidity (called by allocation strategy)
     ///
-    /// **Postconditions:**
-    /// - Returns unique `commitment_id`
-    /// - `get_commitment(commitment_id).owner == owner`
-    /// - `get_commitment(commitment_id).amount == amount`
-    /// - `get_commitment(commitment_id).status == "active"`
-    /// - `get_total_commitments() == old(get_total_commitments()) + 1`
-    /// - `reentrancy_guard == false`
+    /// `caller` must be the owner, an operator approved for `OperatorScope::Allocate`, or
+    /// the registered allocation contract.
     ///
-    /// **Invariants Maintained:**
-    /// - INV-1: Total commitments consistency
-    /// - INV-2: Commitment balance conservation
-    /// - INV-3: Owner commitment list consistency
-    /// - INV-4: Reentrancy guard invariant
+    /// Sends `amount` of the commitment's asset to `target_pool`, which must implement
+    /// `AllocationPoolInterface`. The funds stay part of the commitment's `current_value`
+    /// and are recorded in its allocation ledger until `deallocate` (or settlement, exit
+    /// or clawback) recalls them; at most `current_value` can be out at once.
     ///
-    /// **Security Properties:**
-    /// - SP-1: Reentrancy protection
-    /// - SP-2: Access control
-    /// - SP-4: State consistency
-    /// - SP-5: Token conservation
-    pub fn create_commitment(
+    /// # Reentrancy Protection
+    /// Uses checks-effects-interactions pattern with reentrancy guard.
+    pub fn allocate(
         e: Env,
-        owner: Address,
+        commitment_id: String,
+        caller: Address,
+        target_pool: Address,
         amount: i128,
-        asset_address: Address,
-        rules: CommitmentRules,
-    ) -> String {
+    ) {
+        // Reentrancy protection
+        require_no_reentrancy(&e);
+        set_reentrancy_guard(&e, true);
+
+        // Check if contract is paused
+        Pausable::require_not_paused(&e);
+        EmergencyControl::require_not_emergency(&e);
+
+        // Rate limit allocations per target pool address
+        let fn_symbol = symbol_short!("alloc");
+        RateLimiter::check(&e, &target_pool, &fn_symbol);
+
+        // CHECKS: Validate inputs and commitment
+        if amount <= 0 {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::InvalidAmount, "allocate");
+        }
+
+        let commitment = read_commitment(&e, &commitment_id).unwrap_or_else(|| {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::CommitmentNotFound, "allocate")
+        });
+
+        // Verify caller is the owner, an approved operator or the allocation contract
+        caller.require_auth();
+        let allocation_contract = e
+            .storage()
+            .instance()
+            .get::<_, Address>(&DataKey::AllocationContract);
+        if commitment.owner != caller
+            && allocation_contract != Some(caller.clone())
+            && !is_operator_for(&e, &commitment, &caller, OperatorScope::Allocate)
+        {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::Unauthorized, "allocate");
+        }
+
+        // Verify commitment is active
+        if commitment.status != CommitmentStatus::Active {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::NotActive, "allocate");
+        }
+
+        // Verify sufficient unallocated value
+        let mut allocations = read_allocations(&e, &commitment_id);
+        let allocated = allocated_total(&allocations);
+        if SafeMath::sub(commitment.current_value, allocated) < amount {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::InsufficientBalance, "allocate");
+        }
+        if read_basket_legs(&e, &commitment_id).is_some() {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::BasketNotSupported, "allocate");
+        }
+        let allowed_pool = read_commitment_template(&e, &commitment_id)
+            .and_then(|template_id| read_template(&e, &template_id))
+            .and_then(|template| template.allocation_pool);
+        if allowed_pool.is_some_and(|pool| pool != target_pool) {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::PoolNotAllowed, "allocate");
+        }
+
+        // EFFECTS: Record the allocation before external call
+        let pool_amount = allocations.get(target_pool.clone()).unwrap_or(0);
+        allocations.set(target_pool.clone(), SafeMath::add(pool_amount, amount));
+        write_allocations(&e, &commitment_id, &allocations);
+
+        // INTERACTIONS: External call (token transfer)
+        // Transfer assets to target pool
+        let contract_address = e.current_contract_address();
+        let token_client = token::Client::new(&e, &commitment.asset_address);
+        token_client.transfer(&contract_address, &target_pool, &amount);
+
+        // Clear reentrancy guard
+        set_reentrancy_guard(&e, false);
+
+        // Emit allocation event
+        e.events().publish(
+            (symbol_short!("Alloc"), commitment_id, target_pool),
+            (amount, e.ledger().timestamp()),
+        );
+    }
+
+    /// Pull `amount` allocated to `pool` back into the contract (owner only).
+    ///
+    /// The pool is called through `AllocationPoolInterface::withdraw` and the funds must
+    /// arrive in full, or the call fails and the ledger is unchanged. On a commitment that
+    /// closed through `emergency_withdraw` the recalled funds are passed straight on to
+    /// whoever that withdrawal paid (the owner, or the funder during a clawback window).
+    ///
+    /// # Reentrancy Protection
+    /// Uses checks-effects-interactions pattern with reentrancy guard.
+    pub fn deallocate(e: Env, commitment_id: String, pool: Address, amount: i128) {
         // Reentrancy protection
         require_no_reentrancy(&e);
         set_reentrancy_guard(&e, true);
@@ -405,385 +4544,609 @@ impl CommitmentCoreContract {
         // Check if contract is paused
         Pausable::require_not_paused(&e);
 
-        // Rate limit: per-owner commitment creation
-        let fn_symbol = symbol_short!("create");
-        RateLimiter::check(&e, &owner, &fn_symbol);
-
-        // Validate amount > 0 using shared utilities
-        Validation::require_positive(amount);
+        // CHECKS
+        let commitment = read_commitment(&e, &commitment_id).unwrap_or_else(|| {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::CommitmentNotFound, "deallocate")
+        });
+        commitment.owner.require_auth();
 
-        // Validate rules
-        Self::validate_rules(&e, &rules);
+        let mut allocations = read_allocations(&e, &commitment_id);
+        let pool_amount = allocations.get(pool.clone()).unwrap_or(0);
+        if amount <= 0 || amount > pool_amount {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::InvalidAmount, "deallocate");
+        }
 
-        // CHECKS: Verify sufficient balance BEFORE any state modifications (CEI pattern)
-        check_sufficient_balance(&e, &owner, &asset_address, amount);
+        // EFFECTS: Update the ledger; the recall below reverts it if the pool fails
+        if amount == pool_amount {
+            allocations.remove(pool.clone());
+        } else {
+            allocations.set(pool.clone(), pool_amount - amount);
+        }
+        write_allocations(&e, &commitment_id, &allocations);
 
-        // OPTIMIZATION: Read both counters and NFT contract once to minimize storage operations
-        let (current_total, current_tvl, nft_contract) = {
-            let total = e
-                .storage()
-                .instance()
-                .get::<_, u64>(&DataKey::TotalCommitments)
-                .unwrap_or(0);
-            let tvl = e
+        // Funds a pool kept through an emergency withdrawal are paid out as they return,
+        // so they stop counting as locked
+        let recipient = if commitment.status.is_open() {
+            None
+        } else {
+            let key = DataKey::RecallRecipient(commitment_id.clone());
+            let recipient =
+                read_persistent::<Address>(&e, &key).unwrap_or(commitment.owner.clone());
+            if allocations.is_empty() {
+                e.storage().persistent().remove(&key);
+            }
+            adjust_asset_total_locked(&e, &commitment.asset_address, -amount);
+            let current_tvl = e
                 .storage()
                 .instance()
                 .get::<_, i128>(&DataKey::TotalValueLocked)
                 .unwrap_or(0);
-            let nft = e
-                .storage()
+            e.storage()
                 .instance()
-                .get::<_, Address>(&DataKey::NftContract)
-                .unwrap_or_else(|| {
-                    set_reentrancy_guard(&e, false);
-                    fail(&e, CommitmentError::NotInitialized, "create_commitment")
-                });
-            (total, tvl, nft)
+                .set(&DataKey::TotalValueLocked, &(current_tvl - amount));
+            Some(recipient)
         };
 
-        // Generate unique commitment ID using counter
-        let commitment_id = Self::generate_commitment_id(&e, current_total);
-
-        // CHECKS: Validate commitment doesn't already exist
-        if has_commitment(&e, &commitment_id) {
+        // INTERACTIONS
+        if let Err(err) = recall_allocation(&e, &commitment, &pool, amount) {
             set_reentrancy_guard(&e, false);
-            fail(&e, CommitmentError::InvalidStatus, "create_commitment");
+            fail(&e, err, "deallocate");
+        }
+        if let Some(recipient) = recipient {
+            token::Client::new(&e, &commitment.asset_address).transfer(
+                &e.current_contract_address(),
+                &recipient,
+                &amount,
+            );
         }
 
-        // EFFECTS: Update state before external calls
-        // Calculate expiration timestamp using shared utilities
-        let current_timestamp = TimeUtils::now(&e);
-        let expires_at = TimeUtils::calculate_expiration(&e, rules.duration_days);
+        set_reentrancy_guard(&e, false);
+    }
 
-        // Create commitment data
-        let commitment = Commitment {
-            commitment_id: commitment_id.clone(),
-            owner: owner.clone(),
-            nft_token_id: 0, // Will be set after NFT mint
-            rules: rules.clone(),
-            amount,
-            asset_address: asset_address.clone(),
-            created_at: current_timestamp,
-            expires_at,
-            current_value: amount, // Initially same as amount
-            status: String::from_str(&e, "active"),
-        };
+    /// Funds a commitment has allocated out, by pool
+    pub fn get_allocations(e: Env, commitment_id: String) -> Map<Address, i128> {
+        read_allocations(&e, &commitment_id)
+    }
 
-        // Store commitment data (before external calls)
-        set_commitment(&e, &commitment);
+    /// Configure rate limits for this contract's functions.
+    ///
+    /// This function is restricted to the contract admin.
+    pub fn set_rate_limit(
+        e: Env,
+        caller: Address,
+        function: Symbol,
+        window_seconds: u64,
+        max_calls: u32,
+    ) {
+        require_admin(&e, &caller);
+        RateLimiter::set_limit(&e, &function, window_seconds, max_calls);
+    }
 
-        // Update owner's commitment list
-        let mut owner_commitments = e
-            .storage()
+    /// Set or clear rate limit exemption for an address.
+    ///
+    /// This function is restricted to the contract admin.
+    pub fn set_rate_limit_exempt(e: Env, caller: Address, address: Address, exempt: bool) {
+        require_admin(&e, &caller);
+        RateLimiter::set_exempt(&e, &address, exempt);
+    }
+
+    pub fn add_updater(e: Env, caller: Address, updater: Address) {
+        require_admin(&e, &caller);
+        add_authorized_updater(&e, &updater);
+    }
+
+    pub fn remove_updater(e: Env, caller: Address, updater: Address) {
+        require_admin(&e, &caller);
+        remove_authorized_updater(&e, &updater);
+    }
+
+    pub fn get_authorized_updaters(e: Env) -> Vec<Address> {
+        e.storage()
             .instance()
-            .get::<_, Vec<String>>(&DataKey::OwnerCommitments(owner.clone()))
-            .unwrap_or(Vec::new(&e));
-        owner_commitments.push_back(commitment_id.clone());
-        e.storage().instance().set(
-            &DataKey::OwnerCommitments(owner.clone()),
-            &owner_commitments,
+            .get::<_, Vec<Address>>(&DataKey::AuthorizedUpdaters)
+            .unwrap_or(Vec::new(&e))
+    }
+
+    /// Extend the TTL of commitments and their owner indexes in persistent storage (admin only).
+    ///
+    /// Entries are extended on every read and write; this covers commitments that sit
+    /// untouched for long periods. Unknown or not-yet-migrated ids are skipped.
+    /// Returns the number of commitments extended.
+    pub fn extend_ttl(e: Env, caller: Address, commitment_ids: Vec<String>) -> u32 {
+        require_admin(&e, &caller);
+
+        let mut extended: u32 = 0;
+        for commitment_id in commitment_ids.iter() {
+            let key = DataKey::Commitment(commitment_id.clone());
+            if !e.storage().persistent().has(&key) {
+                continue;
+            }
+            // Reading the commitment extends its own entry
+            let commitment = match read_commitment(&e, &commitment_id) {
+                Some(commitment) => commitment,
+                None => continue,
+            };
+
+            let owner_key = DataKey::OwnerCommitments(commitment.owner);
+            if e.storage().persistent().has(&owner_key) {
+                extend_persistent_ttl(&e, &owner_key);
+            }
+            for side_key in [
+                DataKey::GraceDeadline(commitment_id.clone()),
+                DataKey::FeeCheck(commitment_id.clone()),
+                DataKey::PriceMark(commitment_id.clone()),
+                DataKey::MarkedValue(commitment_id.clone()),
+                DataKey::BasketLegs(commitment_id.clone()),
+            ] {
+                if e.storage().persistent().has(&side_key) {
+                    extend_persistent_ttl(&e, &side_key);
+                }
+            }
+            extend_index_ttl(
+                &e,
+                &CommitmentIndex::Status(commitment.status),
+                &commitment_id,
+            );
+            extend_index_ttl(
+                &e,
+                &CommitmentIndex::Asset(commitment.asset_address),
+                &commitment_id,
+            );
+            extended += 1;
+        }
+
+        e.events().publish(
+            (symbol_short!("TtlExt"),),
+            (extended, e.ledger().timestamp()),
         );
+        extended
+    }
 
-        // OPTIMIZATION: Increment both counters using already-read values
-        e.storage()
+    /// One-time migration of stored commitments to the current layout (`STORAGE_VERSION`):
+    /// moves commitments and owner indexes from instance to persistent storage and
+    /// rewrites string status/type fields as enums (admin only).
+    ///
+    /// Processes up to `limit` commitments per call, resuming where the previous call
+    /// stopped, so large deployments can migrate across several transactions. Reads fall
+    /// back to instance storage and decode older records until an entry has been rewritten.
+    /// Returns the number of commitments still to be scanned; 0 means the migration is complete.
+    pub fn migrate_storage(e: Env, caller: Address, limit: u32) -> u64 {
+        require_admin(&e, &caller);
+
+        if read_storage_version(&e) >= STORAGE_VERSION {
+            fail(&e, CommitmentError::AlreadyMigrated, "migrate_storage");
+        }
+
+        let total = e
+            .storage()
             .instance()
-            .set(&DataKey::TotalCommitments, &(current_total + 1));
-        e.storage()
+            .get::<_, u64>(&DataKey::TotalCommitments)
+            .unwrap_or(0);
+        let start = e
+            .storage()
             .instance()
-            .set(&DataKey::TotalValueLocked, &(current_tvl + amount));
+            .get::<_, u64>(&DataKey::StorageMigrationCursor)
+            .unwrap_or(0);
+        let end = core::cmp::min(total, start + limit as u64);
 
-        // INTERACTIONS: External calls (token transfer, NFT mint)
-        // Transfer assets from owner to contract
-        let contract_address = e.current_contract_address();
-        transfer_assets(&e, &owner, &contract_address, &asset_address, amount);
+        let mut migrated: u32 = 0;
+        for counter in start..end {
+            let commitment_id = Self::generate_commitment_id(&e, counter);
+            if migrate_commitment_entry(&e, &commitment_id) {
+                migrated += 1;
+            }
+        }
 
-        // Mint NFT
-        let nft_token_id = call_nft_mint(
-            &e,
-            &nft_contract,
-            &owner,
-            &commitment_id,
-            rules.duration_days,
-            rules.max_loss_percent,
-            &rules.commitment_type,
-            amount,
-            &asset_address,
+        if end >= total {
+            e.storage()
+                .instance()
+                .set(&DataKey::StorageVersion, &STORAGE_VERSION);
+            e.storage()
+                .instance()
+                .remove(&DataKey::StorageMigrationCursor);
+        } else {
+            e.storage()
+                .instance()
+                .set(&DataKey::StorageMigrationCursor, &end);
+        }
+
+        e.events().publish(
+            (symbol_short!("StoreMig"),),
+            (start, end, migrated, e.ledger().timestamp()),
         );
+        total - end
+    }
 
-        // Update commitment with NFT token ID
-        let mut updated_commitment = commitment;
-        updated_commitment.nft_token_id = nft_token_id;
-        set_commitment(&e, &updated_commitment);
+    /// Layout version of stored commitment records; `STORAGE_VERSION` once migrated.
+    pub fn get_storage_version(e: Env) -> u32 {
+        read_storage_version(&e)
+    }
 
-        // Clear reentrancy guard
-        set_reentrancy_guard(&e, false);
+    /// Get current on-chain version (0 if legacy/uninitialized).
+    pub fn get_version(e: Env) -> u32 {
+        read_version(&e)
+    }
+
+    /// Upgrade contract WASM (admin-only).
+    ///
+    /// If a time lock is configured, `time_lock_action` must name an executed `Upgrade`
+    /// action on it targeting this contract whose data is the lowercase hex of
+    /// `new_wasm_hash`; each action authorizes a single upgrade. Run `migrate`
+    /// afterwards if the new code's `CURRENT_VERSION` is ahead of `get_version`.
+    pub fn upgrade(
+        e: Env,
+        caller: Address,
+        new_wasm_hash: BytesN<32>,
+        time_lock_action: Option<u64>,
+    ) {
+        require_admin(&e, &caller);
+        require_valid_wasm_hash(&e, &new_wasm_hash);
+        require_time_lock_approval(
+            &e,
+            time_lock_action,
+            TimeLockActionType::Upgrade,
+            &hex_string(&e, &new_wasm_hash),
+            "upgrade",
+        );
 
-        // Emit creation event
         e.events().publish(
+            (symbol_short!("Upgraded"),),
             (
-                symbol_short!("Created"),
-                commitment_id.clone(),
-                owner.clone(),
+                new_wasm_hash.clone(),
+                read_version(&e),
+                e.ledger().timestamp(),
             ),
-            (amount, rules, nft_token_id, e.ledger().timestamp()),
         );
-        commitment_id
+        e.deployer().update_current_contract_wasm(new_wasm_hash);
     }
 
-    /// Get commitment details
-    pub fn get_commitment(e: Env, commitment_id: String) -> Commitment {
-        read_commitment(&e, &commitment_id)
-            .unwrap_or_else(|| fail(&e, CommitmentError::CommitmentNotFound, "get_commitment"))
-    }
+    /// Migrate contract state from `from_version` to `CURRENT_VERSION` (admin-only).
+    ///
+    /// Every step only fills in state that is missing, so a migration interrupted or
+    /// re-run from the same version leaves the contract unchanged; once at
+    /// `CURRENT_VERSION` further calls fail with `AlreadyMigrated`. Commitment records
+    /// are migrated separately, in batches, by `migrate_storage`.
+    pub fn migrate(e: Env, caller: Address, from_version: u32) {
+        require_admin(&e, &caller);
 
-    /// Get all commitments for an owner
-    pub fn get_owner_commitments(e: Env, owner: Address) -> Vec<String> {
-        e.storage()
-            .instance()
-            .get::<_, Vec<String>>(&DataKey::OwnerCommitments(owner))
-            .unwrap_or(Vec::new(&e))
-    }
+        let stored_version = read_version(&e);
+        if stored_version == CURRENT_VERSION {
+            fail(&e, CommitmentError::AlreadyMigrated, "migrate");
+        }
+        if from_version != stored_version || from_version > CURRENT_VERSION {
+            fail(&e, CommitmentError::InvalidVersion, "migrate");
+        }
 
-    /// Get total number of commitments
-    pub fn get_total_commitments(e: Env) -> u64 {
-        e.storage()
-            .instance()
-            .get::<_, u64>(&DataKey::TotalCommitments)
-            .unwrap_or(0)
-    }
+        // 0 -> 1: ensure counters and flags that later code expects exist
+        let instance = e.storage().instance();
+        if !instance.has(&DataKey::TotalCommitments) {
+            instance.set(&DataKey::TotalCommitments, &0u64);
+        }
+        if !instance.has(&DataKey::TotalValueLocked) {
+            instance.set(&DataKey::TotalValueLocked, &0i128);
+        }
+        if !instance.has(&DataKey::ReentrancyGuard) {
+            instance.set(&DataKey::ReentrancyGuard, &false);
+        }
+        if !instance.has(&Pausable::PAUSED_KEY) {
+            instance.set(&Pausable::PAUSED_KEY, &false);
+        }
 
-    /// Get total value locked across all active commitments.
-    pub fn get_total_value_locked(e: Env) -> i128 {
-        e.storage()
-            .instance()
-            .get::<_, i128>(&DataKey::TotalValueLocked)
-            .unwrap_or(0)
+        instance.set(&DataKey::Version, &CURRENT_VERSION);
+
+        e.events().publish(
+            (symbol_short!("Migrated"),),
+            (from_version, CURRENT_VERSION, e.ledger().timestamp()),
+        );
     }
 
-    /// Get admin address
-    pub fn get_admin(e: Env) -> Address {
-        e.storage()
-            .instance()
-            .get::<_, Address>(&DataKey::Admin)
-            .unwrap_or_else(|| fail(&e, CommitmentError::NotInitialized, "get_admin"))
+    /// Require upgrades to be approved by `time_lock` (admin-only).
+    ///
+    /// Replacing a configured time lock needs its approval too: `time_lock_action`
+    /// must name an executed `AdminChange` action targeting this contract whose data
+    /// is the new address.
+    pub fn set_time_lock(
+        e: Env,
+        caller: Address,
+        time_lock: Address,
+        time_lock_action: Option<u64>,
+    ) {
+        require_admin(&e, &caller);
+        require_time_lock_approval(
+            &e,
+            time_lock_action,
+            TimeLockActionType::AdminChange,
+            &time_lock.to_string(),
+            "set_time_lock",
+        );
+        e.storage().instance().set(&DataKey::TimeLock, &time_lock);
+
+        e.events().publish(
+            (symbol_short!("TimeLock"),),
+            (time_lock, e.ledger().timestamp()),
+        );
     }
 
-    /// Get NFT contract address
-    pub fn get_nft_contract(e: Env) -> Address {
-        e.storage()
-            .instance()
-            .get::<_, Address>(&DataKey::NftContract)
-            .unwrap_or_else(|| fail(&e, CommitmentError::NotInitialized, "get_nft_contract"))
+    /// Time lock that approves upgrades, if configured.
+    pub fn get_time_lock(e: Env) -> Option<Address> {
+        e.storage().instance().get(&DataKey::TimeLock)
     }
 
-    /// Set allocation contract address (only admin can call)
-    pub fn set_allocation_contract(e: Env, caller: Address, allocation_contract: Address) {
+    /// Turn emergency mode on or off (admin-only).
+    ///
+    /// While on, new commitments, basket commitments, top-ups, renewals and allocations
+    /// are rejected, and `emergency_withdraw` and `emergency_sweep` become available.
+    pub fn set_emergency_mode(e: Env, caller: Address, enabled: bool) {
         require_admin(&e, &caller);
-        e.storage()
-            .instance()
-            .set(&DataKey::AllocationContract, &allocation_contract);
+        EmergencyControl::set_emergency_mode(&e, enabled);
     }
 
-    /// Get allocation contract address
-    pub fn get_allocation_contract(e: Env) -> Option<Address> {
-        e.storage()
-            .instance()
-            .get::<_, Address>(&DataKey::AllocationContract)
+    /// Check whether emergency mode is on
+    pub fn is_emergency_mode(e: Env) -> bool {
+        EmergencyControl::is_emergency_mode(&e)
     }
 
-    /// Update commitment value (called by allocation logic or oracle-fed keeper).
-    /// Persists new_value to commitment.current_value and updates TotalValueLocked.
-    /// 
-    /// # Access Control
-    /// Only the admin or authorized allocation contract can call this function.
-    pub fn update_value(e: Env, caller: Address, commitment_id: String, new_value: i128) {
-        // Access control: only admin or authorized allocation contract can update value
+    /// Return an open commitment's full `current_value` to its owner without penalty.
+    ///
+    /// Only available in emergency mode, and not blocked by pausing. Callable by the
+    /// owner or the admin; funds always go to the owner, or to the funder while a
+    /// sponsored commitment can still be clawed back. Basket legs are paid back in
+    /// full in their own assets. The NFT is settled on a best-effort basis so a failing
+    /// NFT contract cannot keep funds locked. Allocations are recalled the same way:
+    /// whatever a pool fails to return is left out of the payout, stays locked, and is
+    /// paid to the same recipient when `deallocate` recalls it later. Returns the value
+    /// released.
+    pub fn emergency_withdraw(e: Env, commitment_id: String, caller: Address) -> i128 {
+        // Reentrancy protection
+        require_no_reentrancy(&e);
+        set_reentrancy_guard(&e, true);
+
+        EmergencyControl::require_emergency(&e);
+
+        // CHECKS
+        let mut commitment = read_commitment(&e, &commitment_id).unwrap_or_else(|| {
+            set_reentrancy_guard(&e, false);
+            fail(
+                &e,
+                CommitmentError::CommitmentNotFound,
+                "emergency_withdraw",
+            )
+        });
+
+        caller.require_auth();
         let admin = e
             .storage()
             .instance()
             .get::<_, Address>(&DataKey::Admin)
-            .unwrap_or_else(|| fail(&e, CommitmentError::NotInitialized, "update_value"));
-        
-        let allocation_contract = e
-            .storage()
-            .instance()
-            .get::<_, Address>(&DataKey::AllocationContract);
-        
-        let is_authorized = caller == admin || 
-            (allocation_contract.is_some() && caller == allocation_contract.unwrap());
-        
-        if !is_authorized {
-            fail(&e, CommitmentError::Unauthorized, "update_value");
+            .unwrap_or_else(|| {
+                set_reentrancy_guard(&e, false);
+                fail(&e, CommitmentError::NotInitialized, "emergency_withdraw")
+            });
+        if caller != commitment.owner && caller != admin {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::Unauthorized, "emergency_withdraw");
         }
 
-        // Global per-function rate limit (per contract instance)
-        let fn_symbol = symbol_short!("upd_val");
-        let contract_address = e.current_contract_address();
-        RateLimiter::check(&e, &contract_address, &fn_symbol);
-
-        Validation::require_non_negative(new_value);
-
-        let mut commitment = read_commitment(&e, &commitment_id)
-            .unwrap_or_else(|| fail(&e, CommitmentError::CommitmentNotFound, "update_value"));
-
-        let active_status = String::from_str(&e, "active");
-        if commitment.status != active_status {
-            fail(&e, CommitmentError::NotActive, "update_value");
+        if !commitment.status.is_open() {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::NotActive, "emergency_withdraw");
         }
 
-        let old_value = commitment.current_value;
-        commitment.current_value = new_value;
-
-        // Violation detection
-        let loss_percent = if commitment.amount > 0 {
-            (commitment.amount - new_value) * 100 / commitment.amount
-        } else {
-            0
-        };
-
-        let violated = loss_percent > commitment.rules.max_loss_percent as i128;
-        if violated {
-            commitment.status = String::from_str(&e, "violated");
-            e.events().publish(
-                (symbol_short!("Violated"), commitment_id.clone()),
-                (
-                    loss_percent,
-                    commitment.rules.max_loss_percent,
-                    e.ledger().timestamp(),
-                ),
+        // Recall what the pools return; anything a pool keeps stays on the ledger for a
+        // later `deallocate` and is not paid out here
+        let outstanding = unwind_allocations(&e, &commitment);
+
+        // EFFECTS: funds still subject to clawback go back to the funder
+        let recipient =
+            active_clawback_funder(&e, &commitment_id).unwrap_or(commitment.owner.clone());
+        let exit_value = SafeMath::sub(commitment.current_value, outstanding);
+        let payouts = release_commitment(&e, &mut commitment, outstanding);
+        if outstanding > 0 {
+            write_persistent(
+                &e,
+                &DataKey::RecallRecipient(commitment_id.clone()),
+                &recipient,
             );
         }
 
-        set_commitment(&e, &commitment);
+        // INTERACTIONS
+        let contract_address = e.current_contract_address();
+        for (asset, amount) in payouts.iter() {
+            if amount > 0 {
+                token::Client::new(&e, &asset).transfer(&contract_address, &recipient, &amount);
+            }
+        }
 
-        // Update TVL
-        let current_tvl = e
+        if let Some(nft_contract) = e
             .storage()
             .instance()
-            .get::<_, i128>(&DataKey::TotalValueLocked)
-            .unwrap_or(0);
-        e.storage().instance().set(
-            &DataKey::TotalValueLocked,
-            &(current_tvl - old_value + new_value),
-        );
+            .get::<_, Address>(&DataKey::NftContract)
+        {
+            let mut args = Vec::new(&e);
+            args.push_back(contract_address.into_val(&e));
+            args.push_back(commitment.nft_token_id.into_val(&e));
+            let _ =
+                e.try_invoke_contract::<(), Error>(&nft_contract, &Symbol::new(&e, "settle"), args);
+        }
+
+        set_reentrancy_guard(&e, false);
 
         e.events().publish(
-            (symbol_short!("ValUpd"), commitment_id),
-            (old_value, new_value, violated, e.ledger().timestamp()),
+            (symbol_short!("EmgExit"), commitment_id, caller),
+            (recipient, exit_value, e.ledger().timestamp()),
         );
+        exit_value
     }
 
-    /// Check if commitment rules are violated
-    /// Returns true if any rule violation is detected (loss limit or duration)
-    ///
-    /// # Formal Verification
-    /// **Preconditions:**
-    /// - `commitment_id` exists
-    ///
-    /// **Postconditions:**
-    /// - Returns `true` if `loss_percent > max_loss_percent OR current_time >= expires_at`
-    /// - Returns `false` otherwise
-    /// - Pure function (no state changes)
-    ///
-    /// **Invariants Maintained:**
-    /// - INV-2: Commitment balance conservation
+    /// Sweep untracked dust of `asset` to `to` (admin-only, emergency mode only).
     ///
-    /// **Security Properties:**
-    /// - SP-4: State consistency (read-only)
-    pub fn check_violations(e: Env, commitment_id: String) -> bool {
-        let commitment = read_commitment(&e, &commitment_id)
-            .unwrap_or_else(|| fail(&e, CommitmentError::CommitmentNotFound, "check_violations"));
+    /// Only the balance above what the contract owes is moved: value locked in open
+    /// commitments, unclaimed treasury penalties and unclaimed redistributed penalties
+    /// stay in place. Locked value comes from the per-asset counter, which does not cover
+    /// positions opened before per-asset tracking; let those close before sweeping.
+    /// Returns the amount swept, which may be 0.
+    pub fn emergency_sweep(e: Env, caller: Address, asset: Address, to: Address) -> i128 {
+        require_admin(&e, &caller);
+        EmergencyControl::require_emergency(&e);
+        require_no_reentrancy(&e);
 
-        // Skip check if already settled or violated
-        let active_status = String::from_str(&e, "active");
-        if commitment.status != active_status {
-            return false; // Already processed
+        let token_client = token::Client::new(&e, &asset);
+        let contract_address = e.current_contract_address();
+        let balance = token_client.balance(&contract_address);
+        let dust = SafeMath::sub(balance, tracked_asset_liabilities(&e, &asset)).max(0);
+        if dust == 0 {
+            return 0;
         }
 
-        let current_time = e.ledger().timestamp();
-
-        // Check loss limit violation
-        // Calculate loss percentage using shared utilities, but handle zero-amount
-        // commitments gracefully to avoid panics. A zero-amount commitment cannot
-        // meaningfully violate a loss limit, so we treat its loss percent as 0.
-        let loss_percent = if commitment.amount > 0 {
-            SafeMath::loss_percent(commitment.amount, commitment.current_value)
-        } else {
-            0
-        };
-
-        // Convert max_loss_percent (u32) to i128 for comparison
-        let max_loss = commitment.rules.max_loss_percent as i128;
-        let loss_violated = loss_percent > max_loss;
-
-        // Check duration violation (expired)
-        let duration_violated = current_time >= commitment.expires_at;
+        set_reentrancy_guard(&e, true);
+        token_client.transfer(&contract_address, &to, &dust);
+        set_reentrancy_guard(&e, false);
 
-        let violated = loss_violated || duration_violated;
+        e.events().publish(
+            (symbol_short!("EmgSweep"), asset, to),
+            (dust, e.ledger().timestamp()),
+        );
+        dust
+    }
 
-        if violated {
-            // Emit violation event
-            e.events().publish(
-                (symbol_short!("Violated"), commitment_id),
-                (symbol_short!("RuleViol"), e.ledger().timestamp()),
-            );
-        }
+    /// Let `operator` act on all of `owner`'s commitments within `scopes` until
+    /// `expires_at` (ledger timestamp, `None` for no expiry). Replaces any earlier
+    /// owner-wide approval of the same operator.
+    ///
+    /// Scopes: `EarlyExit`, `Renew` and `Allocate` (core's `allocate` as well as
+    /// allocation_logic). Payouts still go to the owner.
+    pub fn approve_operator(
+        e: Env,
+        owner: Address,
+        operator: Address,
+        scopes: Vec<OperatorScope>,
+        expires_at: Option<u64>,
+    ) {
+        owner.require_auth();
+        let approval = new_operator_approval(&e, &owner, &operator, scopes, expires_at)
+            .unwrap_or_else(|err| fail(&e, err, "approve_operator"));
+        write_persistent(
+            &e,
+            &DataKey::OwnerOperator(owner.clone(), operator.clone()),
+            &approval,
+        );
 
-        // Return true if any violation exists
-        violated
+        e.events().publish(
+            (symbol_short!("OpApprove"), owner, operator),
+            (approval, e.ledger().timestamp()),
+        );
     }
 
-    /// Get detailed violation information
-    /// Returns a tuple: (has_violations, loss_violated, duration_violated, loss_percent, time_remaining)
-    pub fn get_violation_details(e: Env, commitment_id: String) -> (bool, bool, bool, i128, u64) {
+    /// Let `operator` act on one of `owner`'s commitments within `scopes` until
+    /// `expires_at`. The approval lapses if the commitment changes owner.
+    pub fn approve_commitment_operator(
+        e: Env,
+        owner: Address,
+        commitment_id: String,
+        operator: Address,
+        scopes: Vec<OperatorScope>,
+        expires_at: Option<u64>,
+    ) {
+        owner.require_auth();
         let commitment = read_commitment(&e, &commitment_id).unwrap_or_else(|| {
             fail(
                 &e,
                 CommitmentError::CommitmentNotFound,
-                "get_violation_details",
+                "approve_commitment_operator",
             )
         });
+        if commitment.owner != owner {
+            fail(
+                &e,
+                CommitmentError::Unauthorized,
+                "approve_commitment_operator",
+            );
+        }
+        let approval = new_operator_approval(&e, &owner, &operator, scopes, expires_at)
+            .unwrap_or_else(|err| fail(&e, err, "approve_commitment_operator"));
+        write_persistent(
+            &e,
+            &DataKey::CommitmentOperator(commitment_id.clone(), owner, operator.clone()),
+            &approval,
+        );
 
-        let current_time = e.ledger().timestamp();
+        e.events().publish(
+            (symbol_short!("OpApprove"), commitment_id, operator),
+            (approval, e.ledger().timestamp()),
+        );
+    }
 
-        // Calculate loss percentage
-        let loss_amount = commitment.amount - commitment.current_value;
-        let loss_percent = if commitment.amount > 0 {
-            (loss_amount * 100) / commitment.amount
-        } else {
-            0
-        };
+    /// Withdraw an owner-wide operator approval.
+    pub fn revoke_operator(e: Env, owner: Address, operator: Address) {
+        owner.require_auth();
+        e.storage()
+            .persistent()
+            .remove(&DataKey::OwnerOperator(owner.clone(), operator.clone()));
 
-        // Check loss limit violation
-        let max_loss = commitment.rules.max_loss_percent as i128;
-        let loss_violated = loss_percent > max_loss;
+        e.events().publish(
+            (symbol_short!("OpRevoke"), owner, operator),
+            e.ledger().timestamp(),
+        );
+    }
 
-        // Check duration violation
-        let duration_violated = current_time >= commitment.expires_at;
+    /// Withdraw an operator approval for one commitment.
+    pub fn revoke_commitment_operator(
+        e: Env,
+        owner: Address,
+        commitment_id: String,
+        operator: Address,
+    ) {
+        owner.require_auth();
+        e.storage()
+            .persistent()
+            .remove(&DataKey::CommitmentOperator(
+                commitment_id.clone(),
+                owner,
+                operator.clone(),
+            ));
 
-        // Calculate time remaining (0 if expired)
-        let time_remaining = if current_time < commitment.expires_at {
-            commitment.expires_at - current_time
-        } else {
-            0
-        };
+        e.events().publish(
+            (symbol_short!("OpRevoke"), commitment_id, operator),
+            e.ledger().timestamp(),
+        );
+    }
 
-        let has_violations = loss_violated || duration_violated;
+    /// Owner-wide approval of `operator`, if any (expired approvals included).
+    pub fn get_operator_approval(
+        e: Env,
+        owner: Address,
+        operator: Address,
+    ) -> Option<OperatorApproval> {
+        read_persistent(&e, &DataKey::OwnerOperator(owner, operator))
+    }
 
-        (
-            has_violations,
-            loss_violated,
-            duration_violated,
-            loss_percent,
-            time_remaining,
+    /// Approval of `operator` for one commitment by its current owner, if any.
+    pub fn get_commitment_operator_approval(
+        e: Env,
+        commitment_id: String,
+        operator: Address,
+    ) -> Option<OperatorApproval> {
+        let owner = read_commitment(&e, &commitment_id)?.owner;
+        read_persistent(
+            &e,
+            &DataKey::CommitmentOperator(commitment_id, owner, operator),
         )
     }
 
-    /// Settle commitment at maturity
+    /// Funding of a commitment created with `create_commitment_for`, if any.
+    pub fn get_sponsorship(e: Env, commitment_id: String) -> Option<Sponsorship> {
+        read_sponsorship(&e, &commitment_id)
+    }
+
+    /// Return a sponsored commitment's full `current_value` to its funder, without
+    /// penalty (funder only).
     ///
-    /// # Reentrancy Protection
-    /// Uses checks-effects-interactions pattern with reentrancy guard.
-    pub fn settle(e: Env, commitment_id: String) {
+    /// Only available before the `clawback_until` deadline given to
+    /// `create_commitment_for`. The commitment closes as `EarlyExit` and its NFT is
+    /// settled. Returns the amount clawed back.
+    pub fn clawback(e: Env, commitment_id: String, funder: Address) -> i128 {
         // Reentrancy protection
         require_no_reentrancy(&e);
         set_reentrancy_guard(&e, true);
@@ -791,266 +5154,594 @@ impl CommitmentCoreContract {
         // Check if contract is paused
         Pausable::require_not_paused(&e);
 
-        // CHECKS: Get and validate commitment
+        // CHECKS
         let mut commitment = read_commitment(&e, &commitment_id).unwrap_or_else(|| {
             set_reentrancy_guard(&e, false);
-            fail(&e, CommitmentError::CommitmentNotFound, "settle")
+            fail(&e, CommitmentError::CommitmentNotFound, "clawback")
         });
 
-        // Verify commitment is expired
-        let current_time = e.ledger().timestamp();
-        if current_time < commitment.expires_at {
+        funder.require_auth();
+        match read_sponsorship(&e, &commitment_id) {
+            Some(sponsorship) if sponsorship.funder == funder => {}
+            Some(_) => {
+                set_reentrancy_guard(&e, false);
+                fail(&e, CommitmentError::Unauthorized, "clawback");
+            }
+            None => {
+                set_reentrancy_guard(&e, false);
+                fail(&e, CommitmentError::ClawbackUnavailable, "clawback");
+            }
+        }
+        if active_clawback_funder(&e, &commitment_id).is_none() {
             set_reentrancy_guard(&e, false);
-            fail(&e, CommitmentError::NotExpired, "settle");
+            fail(&e, CommitmentError::ClawbackUnavailable, "clawback");
         }
-
-        // Verify commitment is active
-        let active_status = String::from_str(&e, "active");
-        if commitment.status != active_status {
+        if !commitment.status.is_open() {
             set_reentrancy_guard(&e, false);
-            fail(&e, CommitmentError::NotActive, "settle");
+            fail(&e, CommitmentError::NotActive, "clawback");
+        }
+        if unwind_allocations(&e, &commitment) > 0 {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::AllocationNotReturned, "clawback");
         }
 
-        // EFFECTS: Update state before external calls
-        let settlement_amount = commitment.current_value;
-        commitment.status = String::from_str(&e, "settled");
-        set_commitment(&e, &commitment);
-
-        // Decrease total value locked
-        let current_tvl = e
-            .storage()
-            .instance()
-            .get::<_, i128>(&DataKey::TotalValueLocked)
-            .unwrap_or(0);
-        let new_tvl = current_tvl - settlement_amount;
-        e.storage()
-            .instance()
-            .set(&DataKey::TotalValueLocked, &new_tvl);
-
-        // INTERACTIONS: External calls (token transfer, NFT settlement)
-        // Transfer assets back to owner
-        let contract_address = e.current_contract_address();
-        let token_client = token::Client::new(&e, &commitment.asset_address);
-        token_client.transfer(&contract_address, &commitment.owner, &settlement_amount);
-
-        // Call NFT contract to mark NFT as settled (pass self as caller for access control)
         let nft_contract = e
             .storage()
             .instance()
             .get::<_, Address>(&DataKey::NftContract)
             .unwrap_or_else(|| {
                 set_reentrancy_guard(&e, false);
-                fail(&e, CommitmentError::NotInitialized, "settle")
+                fail(&e, CommitmentError::NotInitialized, "clawback")
             });
+
+        // EFFECTS
+        let clawed_back = commitment.current_value;
+        let payouts = release_commitment(&e, &mut commitment, 0);
+
+        // INTERACTIONS
+        let contract_address = e.current_contract_address();
+        for (asset, amount) in payouts.iter() {
+            if amount > 0 {
+                token::Client::new(&e, &asset).transfer(&contract_address, &funder, &amount);
+            }
+        }
+
         let mut args = Vec::new(&e);
         args.push_back(contract_address.into_val(&e));
         args.push_back(commitment.nft_token_id.into_val(&e));
         e.invoke_contract::<()>(&nft_contract, &Symbol::new(&e, "settle"), args);
 
-        // Clear reentrancy guard
         set_reentrancy_guard(&e, false);
 
-        // Emit settlement event
         e.events().publish(
-            (symbol_short!("Settled"), commitment_id),
-            (settlement_amount, e.ledger().timestamp()),
+            (symbol_short!("ClawBack"), commitment_id, funder),
+            (commitment.owner, clawed_back, e.ledger().timestamp()),
+        );
+        clawed_back
+    }
+
+    /// Claimed, vested and releasable amounts of a commitment under its release schedule.
+    pub fn get_vesting(e: Env, commitment_id: String) -> VestingStatus {
+        let commitment = read_commitment(&e, &commitment_id)
+            .unwrap_or_else(|| fail(&e, CommitmentError::CommitmentNotFound, "get_vesting"));
+        vesting_status(&e, &commitment)
+    }
+
+    /// Pay the owner what the commitment's release schedule has vested so far and not yet
+    /// been claimed (owner only). Returns the amount paid.
+    ///
+    /// Before expiry the claim comes out of `current_value`, and the principal (`amount`)
+    /// is reduced in the same proportion as in `withdraw_partial`; the NFT stays active.
+    /// Once expired everything has vested, so the claim settles the commitment as
+    /// `settle` would and the NFT is marked settled. The claim always pays out: a
+    /// commitment with `auto_rollover` does not roll over on this path.
+    ///
+    /// # Reentrancy Protection
+    /// Uses checks-effects-interactions pattern with reentrancy guard.
+    pub fn claim_vested(e: Env, commitment_id: String) -> i128 {
+        // Reentrancy protection
+        require_no_reentrancy(&e);
+        set_reentrancy_guard(&e, true);
+
+        // Check if contract is paused
+        Pausable::require_not_paused(&e);
+
+        // CHECKS
+        let mut commitment = read_commitment(&e, &commitment_id).unwrap_or_else(|| {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::CommitmentNotFound, "claim_vested")
+        });
+        commitment.owner.require_auth();
+        if !commitment.status.is_open() {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::NotActive, "claim_vested");
+        }
+        if active_clawback_funder(&e, &commitment_id).is_some() {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::ClawbackActive, "claim_vested");
+        }
+
+        let vesting = vesting_status(&e, &commitment);
+        if vesting.releasable <= 0 {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::InvalidAmount, "claim_vested");
+        }
+
+        let claimed = if e.ledger().timestamp() >= commitment.expires_at {
+            settle_commitment(&e, &commitment_id, None, false).unwrap_or_else(|err| {
+                set_reentrancy_guard(&e, false);
+                fail(&e, err, "claim_vested")
+            })
+        } else {
+            let nft_contract = e
+                .storage()
+                .instance()
+                .get::<_, Address>(&DataKey::NftContract)
+                .unwrap_or_else(|| {
+                    set_reentrancy_guard(&e, false);
+                    fail(&e, CommitmentError::NotInitialized, "claim_vested")
+                });
+
+            let claimed = vesting.releasable;
+            require_unallocated(&e, &commitment, claimed, "claim_vested");
+
+            // EFFECTS: Reduce the principal pro-rata to the share of current value claimed
+            let basis_removed = pro_rata(commitment.amount, claimed, commitment.current_value);
+            commitment.amount = SafeMath::sub(commitment.amount, basis_removed);
+            commitment.current_value = SafeMath::sub(commitment.current_value, claimed);
+            set_commitment(&e, &commitment);
+
+            let current_tvl = e
+                .storage()
+                .instance()
+                .get::<_, i128>(&DataKey::TotalValueLocked)
+                .unwrap_or(0);
+            e.storage()
+                .instance()
+                .set(&DataKey::TotalValueLocked, &(current_tvl - claimed));
+            adjust_asset_value_locked(&e, &commitment.owner, &commitment.asset_address, -claimed);
+
+            // INTERACTIONS: External calls (token transfer, NFT metadata update)
+            token::Client::new(&e, &commitment.asset_address).transfer(
+                &e.current_contract_address(),
+                &commitment.owner,
+                &claimed,
+            );
+            call_nft_update_initial_amount(
+                &e,
+                &nft_contract,
+                commitment.nft_token_id,
+                commitment.amount,
+            );
+            claimed
+        };
+
+        let claimed_total = SafeMath::add(vesting.claimed_amount, claimed);
+        write_persistent(
+            &e,
+            &DataKey::VestingClaimed(commitment_id.clone()),
+            &claimed_total,
+        );
+
+        set_reentrancy_guard(&e, false);
+
+        e.events().publish(
+            (symbol_short!("Vested"), commitment_id, commitment.owner),
+            (claimed, claimed_total, e.ledger().timestamp()),
         );
+        claimed
     }
 
-    pub fn early_exit(e: Env, commitment_id: String, caller: Address) {
+    /// Split an active commitment into several with the same owner, rules and expiry
+    /// (owner only). Returns the ids, in the order of `amounts`.
+    ///
+    /// `amounts` must be positive and add up to `current_value`. The commitment keeps the
+    /// first amount and a new commitment with its own NFT is created for each of the
+    /// others. Principal, vested claims and the oracle valuation are shared out pro-rata
+    /// and every part keeps the price mark, so each part keeps the original's loss
+    /// percentage. Not available for baskets, while a clawback is active, or with less
+    /// than a day left.
+    ///
+    /// # Reentrancy Protection
+    /// Uses checks-effects-interactions pattern with reentrancy guard.
+    pub fn split(e: Env, commitment_id: String, amounts: Vec<i128>) -> Vec<String> {
         // Reentrancy protection
         require_no_reentrancy(&e);
         set_reentrancy_guard(&e, true);
 
         // Check if contract is paused
         Pausable::require_not_paused(&e);
+        EmergencyControl::require_not_emergency(&e);
 
-        // CHECKS: Get and validate commitment
+        // CHECKS
         let mut commitment = read_commitment(&e, &commitment_id).unwrap_or_else(|| {
             set_reentrancy_guard(&e, false);
-            fail(&e, CommitmentError::CommitmentNotFound, "early_exit")
+            fail(&e, CommitmentError::CommitmentNotFound, "split")
         });
-
-        // Verify caller is owner
-        caller.require_auth();
-        if commitment.owner != caller {
+        commitment.owner.require_auth();
+        if commitment.status != CommitmentStatus::Active {
             set_reentrancy_guard(&e, false);
-            fail(&e, CommitmentError::Unauthorized, "early_exit");
+            fail(&e, CommitmentError::NotActive, "split");
         }
-
-        // Verify commitment is active
-        let active_status = String::from_str(&e, "active");
-        if commitment.status != active_status {
+        if read_basket_legs(&e, &commitment_id).is_some() {
             set_reentrancy_guard(&e, false);
-            fail(&e, CommitmentError::NotActive, "early_exit");
+            fail(&e, CommitmentError::BasketNotSupported, "split");
+        }
+        if active_clawback_funder(&e, &commitment_id).is_some() {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::ClawbackActive, "split");
+        }
+        if !read_allocations(&e, &commitment_id).is_empty() {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::AllocationsOutstanding, "split");
         }
 
-        // EFFECTS: Calculate penalty using shared utilities
-        let penalty_amount = SafeMath::penalty_amount(
-            commitment.current_value,
-            commitment.rules.early_exit_penalty,
-        );
-        let returned_amount = SafeMath::sub(commitment.current_value, penalty_amount);
-
-        // Update commitment status to early_exit
-        commitment.status = String::from_str(&e, "early_exit");
-        commitment.current_value = 0; // All value has been distributed
-        set_commitment(&e, &commitment);
-
-        // Decrease total value locked by full current value (no longer locked)
-        let current_tvl = e
-            .storage()
-            .instance()
-            .get::<_, i128>(&DataKey::TotalValueLocked)
-            .unwrap_or(0);
-        let new_tvl = current_tvl - commitment.current_value;
-        e.storage()
-            .instance()
-            .set(&DataKey::TotalValueLocked, &new_tvl);
-
-        // INTERACTIONS: External calls (token transfer)
-        // Transfer remaining amount (after penalty) to owner
-        let contract_address = e.current_contract_address();
-        let token_client = token::Client::new(&e, &commitment.asset_address);
-
-        if returned_amount > 0 {
-            token_client.transfer(&contract_address, &commitment.owner, &returned_amount);
+        let mut total = 0i128;
+        for amount in amounts.iter() {
+            let size_ok =
+                check_asset_limits(&e, &commitment.owner, &commitment.asset_address, 0, amount)
+                    .is_ok();
+            if amount <= 0 || !size_ok {
+                set_reentrancy_guard(&e, false);
+                fail(&e, CommitmentError::InvalidSplit, "split");
+            }
+            total = SafeMath::add(total, amount);
+        }
+        if amounts.len() < 2
+            || total != commitment.current_value
+            || commitment.expires_at < e.ledger().timestamp() + 86_400
+        {
+            set_reentrancy_guard(&e, false);
+            fail(&e, CommitmentError::InvalidSplit, "split");
         }
 
-        // Call NFT contract to update NFT status (mark as inactive/early_exited)
         let nft_contract = e
             .storage()
             .instance()
             .get::<_, Address>(&DataKey::NftContract)
             .unwrap_or_else(|| {
                 set_reentrancy_guard(&e, false);
-                fail(&e, CommitmentError::NotInitialized, "early_exit")
+                fail(&e, CommitmentError::NotInitialized, "split")
             });
 
-        // Call settle on NFT to mark it as inactive (pass self as caller for access control)
-        let core_address = e.current_contract_address();
-        let mut args = Vec::new(&e);
-        args.push_back(core_address.into_val(&e));
-        args.push_back(commitment.nft_token_id.into_val(&e));
-        e.invoke_contract::<()>(&nft_contract, &Symbol::new(&e, "settle"), args);
+        // EFFECTS: The original keeps the first amount and any rounding remainder; value
+        // locked and TVL are unchanged
+        let original = commitment.clone();
+        let claimed = read_vesting_claimed(&e, &commitment_id);
+        let marked = read_persistent::<i128>(&e, &DataKey::MarkedValue(commitment_id.clone()));
+        let (mut basis_left, mut claimed_left) = (original.amount, claimed);
+        let mut marked_left = marked.unwrap_or(0);
+        for amount in amounts.iter().skip(1) {
+            basis_left -= pro_rata(original.amount, amount, original.current_value);
+            claimed_left -= pro_rata(claimed, amount, original.current_value);
+            marked_left -= pro_rata(marked.unwrap_or(0), amount, original.current_value);
+        }
+        commitment.amount = basis_left;
+        commitment.current_value = amounts.get_unchecked(0);
+        set_commitment(&e, &commitment);
+        if claimed > 0 {
+            write_persistent(
+                &e,
+                &DataKey::VestingClaimed(commitment_id.clone()),
+                &claimed_left,
+            );
+        }
+        if marked.is_some() {
+            write_marked_value(&e, &commitment_id, marked_left);
+        }
+
+        // INTERACTIONS: Each new part mints its NFT; the original's records its new amount
+        let mut ids = Vec::new(&e);
+        ids.push_back(commitment_id.clone());
+        for amount in amounts.iter().skip(1) {
+            let basis = pro_rata(original.amount, amount, original.current_value);
+            let part_id = Self::create_split_part(&e, &original, amount, basis, &nft_contract);
+            if claimed > 0 {
+                write_persistent(
+                    &e,
+                    &DataKey::VestingClaimed(part_id.clone()),
+                    &pro_rata(claimed, amount, original.current_value),
+                );
+            }
+            if let Some(marked) = marked {
+                write_marked_value(
+                    &e,
+                    &part_id,
+                    pro_rata(marked, amount, original.current_value),
+                );
+            }
+            ids.push_back(part_id);
+        }
+        call_nft_update_initial_amount(
+            &e,
+            &nft_contract,
+            commitment.nft_token_id,
+            commitment.amount,
+        );
 
-        // Clear reentrancy guard
         set_reentrancy_guard(&e, false);
 
-        // Emit early exit event with detailed information
         e.events().publish(
-            (
-                symbol_short!("EarlyExt"),
-                commitment_id.clone(),
-                caller.clone(),
-            ),
-            (penalty_amount, returned_amount, e.ledger().timestamp()),
+            (symbol_short!("Split"), commitment_id, commitment.owner),
+            (ids.clone(), e.ledger().timestamp()),
         );
+        ids
     }
 
-    /// Allocate liquidity (called by allocation strategy)
+    /// Merge active commitments of one owner with the same asset and rules into one
+    /// (owner only). Returns the id of the merged commitment.
+    ///
+    /// The commitment that expires last (the first of those if several do) absorbs the
+    /// others' value, principal and vested claims and keeps its term and NFT. The others
+    /// close as `Settled` with no value left, their NFTs are settled and the `Merged`
+    /// event names them. If any of the commitments carries a price mark, the survivor is
+    /// re-marked so each keeps its gain or loss at today's price, which must be fresh.
+    /// Only `Active` commitments merge, so there is no grace window to carry over. Not
+    /// available for baskets or while a clawback is active.
     ///
     /// # Reentrancy Protection
     /// Uses checks-effects-interactions pattern with reentrancy guard.
-    pub fn allocate(e: Env, commitment_id: String, target_pool: Address, amount: i128) {
+    pub fn merge(e: Env, ids: Vec<String>) -> String {
         // Reentrancy protection
         require_no_reentrancy(&e);
         set_reentrancy_guard(&e, true);
 
         // Check if contract is paused
         Pausable::require_not_paused(&e);
+        EmergencyControl::require_not_emergency(&e);
 
-        // Rate limit allocations per target pool address
-        let fn_symbol = symbol_short!("alloc");
-        RateLimiter::check(&e, &target_pool, &fn_symbol);
-
-        // CHECKS: Validate inputs and commitment
-        if amount <= 0 {
+        // CHECKS
+        if ids.len() < 2 {
             set_reentrancy_guard(&e, false);
-            fail(&e, CommitmentError::InvalidAmount, "allocate");
+            fail(&e, CommitmentError::InvalidMerge, "merge");
         }
+        let mut commitments: Vec<Commitment> = Vec::new(&e);
+        for (index, commitment_id) in ids.iter().enumerate() {
+            let commitment = read_commitment(&e, &commitment_id).unwrap_or_else(|| {
+                set_reentrancy_guard(&e, false);
+                fail(&e, CommitmentError::CommitmentNotFound, "merge")
+            });
+            if commitment.status != CommitmentStatus::Active {
+                set_reentrancy_guard(&e, false);
+                fail(&e, CommitmentError::NotActive, "merge");
+            }
+            if read_basket_legs(&e, &commitment_id).is_some() {
+                set_reentrancy_guard(&e, false);
+                fail(&e, CommitmentError::BasketNotSupported, "merge");
+            }
+            if active_clawback_funder(&e, &commitment_id).is_some() {
+                set_reentrancy_guard(&e, false);
+                fail(&e, CommitmentError::ClawbackActive, "merge");
+            }
+            if !read_allocations(&e, &commitment_id).is_empty() {
+                set_reentrancy_guard(&e, false);
+                fail(&e, CommitmentError::AllocationsOutstanding, "merge");
+            }
+            let duplicate = ids.first_index_of(&commitment_id) != Some(index as u32);
+            let mismatch = commitments.first().is_some_and(|first| {
+                first.owner != commitment.owner
+                    || first.asset_address != commitment.asset_address
+                    || first.rules != commitment.rules
+                    || read_commitment_template(&e, &first.commitment_id)
+                        != read_commitment_template(&e, &commitment_id)
+            });
+            if duplicate || mismatch {
+                set_reentrancy_guard(&e, false);
+                fail(&e, CommitmentError::InvalidMerge, "merge");
+            }
+            commitments.push_back(commitment);
+        }
+        let owner = commitments.get_unchecked(0).owner;
+        owner.require_auth();
 
-        let commitment = read_commitment(&e, &commitment_id).unwrap_or_else(|| {
+        let mut survivor = commitments.get_unchecked(0);
+        for commitment in commitments.iter() {
+            if commitment.expires_at > survivor.expires_at {
+                survivor = commitment;
+            }
+        }
+        let mut total_value = 0i128;
+        for commitment in commitments.iter() {
+            total_value = SafeMath::add(total_value, commitment.current_value);
+        }
+        if let Err(err) = check_asset_limits(&e, &owner, &survivor.asset_address, 0, total_value) {
             set_reentrancy_guard(&e, false);
-            fail(&e, CommitmentError::CommitmentNotFound, "allocate")
+            fail(&e, err, "merge");
+        }
+        let marked = commitments
+            .iter()
+            .any(|commitment| read_price_mark(&e, &commitment.commitment_id).is_some());
+        let price = remark_price(&e, &survivor.asset_address, marked).unwrap_or_else(|err| {
+            set_reentrancy_guard(&e, false);
+            fail(&e, err, "merge")
         });
 
-        // Verify commitment is active
-        let active_status = String::from_str(&e, "active");
-        if commitment.status != active_status {
-            set_reentrancy_guard(&e, false);
-            fail(&e, CommitmentError::NotActive, "allocate");
-        }
+        let nft_contract = e
+            .storage()
+            .instance()
+            .get::<_, Address>(&DataKey::NftContract)
+            .unwrap_or_else(|| {
+                set_reentrancy_guard(&e, false);
+                fail(&e, CommitmentError::NotInitialized, "merge")
+            });
 
-        // Verify sufficient balance
-        if commitment.current_value < amount {
-            set_reentrancy_guard(&e, false);
-            fail(&e, CommitmentError::InsufficientBalance, "allocate");
+        // EFFECTS: Fold the others into the survivor; value locked and TVL are unchanged
+        let mut claimed = read_vesting_claimed(&e, &survivor.commitment_id);
+        let mut merged_ids = Vec::new(&e);
+        let mut merged_tokens = Vec::new(&e);
+        for mut commitment in commitments.iter() {
+            if commitment.commitment_id == survivor.commitment_id {
+                continue;
+            }
+            merged_ids.push_back(commitment.commitment_id.clone());
+            survivor.amount = SafeMath::add(survivor.amount, commitment.amount);
+            survivor.current_value =
+                SafeMath::add(survivor.current_value, commitment.current_value);
+            claimed = SafeMath::add(claimed, read_vesting_claimed(&e, &commitment.commitment_id));
+
+            set_status(&e, &mut commitment, CommitmentStatus::Settled);
+            commitment.current_value = 0;
+            set_commitment(&e, &commitment);
+            merged_tokens.push_back(commitment.nft_token_id);
+        }
+        set_commitment(&e, &survivor);
+        if claimed > 0 {
+            write_persistent(
+                &e,
+                &DataKey::VestingClaimed(survivor.commitment_id.clone()),
+                &claimed,
+            );
+        }
+        if let Some(price) = price {
+            let mut marked_total = 0i128;
+            for commitment in commitments.iter() {
+                let mark = read_price_mark(&e, &commitment.commitment_id);
+                marked_total = SafeMath::add(
+                    marked_total,
+                    marked_value(commitment.current_value, mark.as_ref(), &price),
+                );
+            }
+            blend_price_mark(
+                &e,
+                &survivor.commitment_id,
+                survivor.current_value,
+                marked_total,
+                &price,
+            );
         }
 
-        // EFFECTS: Update commitment value before external call
-        let mut updated_commitment = commitment;
-        updated_commitment.current_value = updated_commitment.current_value - amount;
-        set_commitment(&e, &updated_commitment);
-
-        // INTERACTIONS: External call (token transfer)
-        // Transfer assets to target pool
+        // INTERACTIONS: Settle the merged NFTs and re-stamp the survivor's
         let contract_address = e.current_contract_address();
-        let token_client = token::Client::new(&e, &updated_commitment.asset_address);
-        token_client.transfer(&contract_address, &target_pool, &amount);
+        for nft_token_id in merged_tokens.iter() {
+            let mut args = Vec::new(&e);
+            args.push_back(contract_address.clone().into_val(&e));
+            args.push_back(nft_token_id.into_val(&e));
+            e.invoke_contract::<()>(&nft_contract, &Symbol::new(&e, "settle"), args);
+        }
+        call_nft_update_initial_amount(&e, &nft_contract, survivor.nft_token_id, survivor.amount);
 
-        // Clear reentrancy guard
         set_reentrancy_guard(&e, false);
 
-        // Emit allocation event
         e.events().publish(
-            (symbol_short!("Alloc"), commitment_id, target_pool),
-            (amount, e.ledger().timestamp()),
+            (
+                symbol_short!("Merged"),
+                survivor.commitment_id.clone(),
+                owner,
+            ),
+            (merged_ids, survivor.current_value, e.ledger().timestamp()),
         );
+        survivor.commitment_id
     }
+}
 
-    /// Configure rate limits for this contract's functions.
-    ///
-    /// This function is restricted to the contract admin.
-    pub fn set_rate_limit(
-        e: Env,
-        caller: Address,
-        function: Symbol,
-        window_seconds: u64,
-        max_calls: u32,
-    ) {
-        require_admin(&e, &caller);
-        RateLimiter::set_limit(&e, &function, window_seconds, max_calls);
-    }
-
-    /// Set or clear rate limit exemption for an address.
-    ///
-    /// This function is restricted to the contract admin.
-    pub fn set_rate_limit_exempt(e: Env, caller: Address, address: Address, exempt: bool) {
-        require_admin(&e, &caller);
-        RateLimiter::set_exempt(&e, &address, exempt);
+#[contractimpl]
+impl CommitmentInterface for CommitmentCoreContract {
+    /// Get commitment details
+    fn get_commitment(e: Env, commitment_id: String) -> Commitment {
+        read_commitment(&e, &commitment_id)
+            .unwrap_or_else(|| fail(&e, CommitmentError::CommitmentNotFound, "get_commitment"))
     }
 
-    pub fn add_updater(e: Env, caller: Address, updater: Address) {
-        require_admin(&e, &caller);
-        add_authorized_updater(&e, &updater);
+    /// Get all commitments for an owner
+    fn get_owner_commitments(e: Env, owner: Address) -> Vec<String> {
+        read_owner_commitments(&e, &owner)
     }
 
-    pub fn remove_updater(e: Env, caller: Address, updater: Address) {
-        require_admin(&e, &caller);
-        remove_authorized_updater(&e, &updater);
+    /// Get total number of commitments
+    fn get_total_commitments(e: Env) -> u64 {
+        e.storage()
+            .instance()
+            .get::<_, u64>(&DataKey::TotalCommitments)
+            .unwrap_or(0)
     }
 
-    pub fn get_authorized_updaters(e: Env) -> Vec<Address> {
+    /// Get total value locked across all active commitments.
+    fn get_total_value_locked(e: Env) -> i128 {
         e.storage()
             .instance()
-            .get::<_, Vec<Address>>(&DataKey::AuthorizedUpdaters)
-            .unwrap_or(Vec::new(&e))
+            .get::<_, i128>(&DataKey::TotalValueLocked)
+            .unwrap_or(0)
+    }
+
+    /// Check if commitment rules are violated
+    /// Returns true if any rule violation is detected (loss limit or duration)
+    ///
+    /// # Formal Verification
+    /// **Preconditions:**
+    /// - `commitment_id` exists
+    ///
+    /// **Postconditions:**
+    /// - Returns `true` if `loss_percent > max_loss_percent OR current_time >= expires_at`
+    /// - Returns `false` otherwise
+    /// - Pure function (no state changes)
+    ///
+    /// **Invariants Maintained:**
+    /// - INV-2: Commitment balance conservation
+    ///
+    /// **Security Properties:**
+    /// - SP-4: State consistency (read-only)
+    fn check_violations(e: Env, commitment_id: String) -> bool {
+        let commitment = read_commitment(&e, &commitment_id)
+            .unwrap_or_else(|| fail(&e, CommitmentError::CommitmentNotFound, "check_violations"));
+
+        // Skip check if already settled or violated
+        if !commitment.status.is_open() {
+            return false; // Already processed
+        }
+
+        let current_time = e.ledger().timestamp();
+
+        // Check loss limit violation
+        // Calculate loss percentage using shared utilities, but handle zero-amount
+        // commitments gracefully to avoid panics. A zero-amount commitment cannot
+        // meaningfully violate a loss limit, so we treat its loss percent as 0.
+        let loss_percent = if commitment.amount > 0 {
+            SafeMath::loss_percent(commitment.amount, commitment.current_value)
+        } else {
+            0
+        };
+
+        // Convert max_loss_percent (u32) to i128 for comparison.
+        // A breach inside the grace window is not yet a violation.
+        let max_loss = commitment.rules.max_loss_percent as i128;
+        let loss_violated = loss_violation_confirmed(&e, &commitment, loss_percent > max_loss);
+
+        // Check duration violation (expired)
+        let duration_violated = current_time >= commitment.expires_at;
+
+        let violated = loss_violated || duration_violated;
+
+        if violated {
+            // Emit violation event
+            e.events().publish(
+                (symbol_short!("Violated"), commitment_id),
+                (symbol_short!("RuleViol"), e.ledger().timestamp()),
+            );
+        } else if let Some(deadline) = read_grace_deadline(&e, &commitment_id) {
+            // Still inside the grace window: surface the deadline to keepers
+            e.events().publish(
+                (symbol_short!("AtRisk"), commitment_id),
+                (loss_percent, deadline, current_time),
+            );
+        }
+
+        // Return true if any violation exists
+        violated
+    }
+
+    /// Whether `operator` may act on the commitment within `scope`. False for unknown
+    /// commitments.
+    fn is_operator(e: Env, commitment_id: String, operator: Address, scope: OperatorScope) -> bool {
+        read_commitment(&e, &commitment_id)
+            .is_some_and(|commitment| is_operator_for(&e, &commitment, &operator, scope))
     }
 }
 
 #[cfg(test)]
 mod tests;
 
+#[cfg(test)]
+mod emergency_tests;
+
 #[cfg(all(test, feature = "benchmark"))]
 mod benchmarks;
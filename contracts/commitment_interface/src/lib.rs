@@ -3,50 +3,66 @@
 pub mod error;
 pub mod types;
 
-use soroban_sdk::{contract, contractimpl, symbol_short, Address, Env, Symbol};
+use soroban_sdk::{contractclient, symbol_short, Address, Env, String, Symbol, Vec};
 
-use crate::error::Error;
-use crate::types::CommitmentSpec;
+use crate::types::{Commitment, OperatorScope};
 
-/// =======================
-/// Interface Metadata
-/// =======================
+// =======================
+// Interface Metadata
+// =======================
 
-pub const INTERFACE_VERSION: u32 = 1;
+pub const INTERFACE_VERSION: u32 = 3;
 
-/// =======================
-/// Events
-/// =======================
+// =======================
+// Events
+// =======================
 
 pub const COMMITMENT_CREATED: Symbol = symbol_short!("created");
 pub const COMMITMENT_REVOKED: Symbol = symbol_short!("revoked");
 
-/// =======================
-/// Interface Contract
-/// =======================
-
-#[contract]
-pub struct CommitmentInterface;
-
-#[contractimpl]
-impl CommitmentInterface {
-    /// Initialize the commitment system
-    pub fn initialize(_env: Env, _admin: Address) -> Result<(), Error> {
-        unimplemented!("interface only")
-    }
-
-    /// Create a new commitment
-    pub fn create_commitment(_env: Env, _spec: CommitmentSpec) -> Result<u64, Error> {
-        unimplemented!("interface only")
-    }
-
-    /// Fetch an existing commitment
-    pub fn get_commitment(_env: Env, _id: u64) -> Result<CommitmentSpec, Error> {
-        unimplemented!("interface only")
-    }
-
-    /// Revoke a commitment
-    pub fn revoke_commitment(_env: Env, _id: u64) -> Result<(), Error> {
-        unimplemented!("interface only")
-    }
+// =======================
+// Interface
+// =======================
+
+/// Read side of a commitment backend, as used by other contracts.
+///
+/// commitment_core implements this trait; other contracts hold the backend's address
+/// and call it through the generated `CommitmentClient` instead of decoding raw `Val`s,
+/// so a change to `Commitment` fails to compile rather than failing at runtime.
+#[contractclient(name = "CommitmentClient")]
+pub trait CommitmentInterface {
+    /// Fetch a commitment. Panics if it does not exist.
+    fn get_commitment(env: Env, commitment_id: String) -> Commitment;
+
+    /// Ids of the commitments held by `owner`.
+    fn get_owner_commitments(env: Env, owner: Address) -> Vec<String>;
+
+    /// Number of commitments ever created.
+    fn get_total_commitments(env: Env) -> u64;
+
+    /// Value locked across open commitments.
+    fn get_total_value_locked(env: Env) -> i128;
+
+    /// Whether the commitment breaches its loss limit or has expired.
+    fn check_violations(env: Env, commitment_id: String) -> bool;
+
+    /// Whether `operator` holds an unexpired approval covering `scope` for the
+    /// commitment, granted by its current owner.
+    fn is_operator(
+        env: Env,
+        commitment_id: String,
+        operator: Address,
+        scope: OperatorScope,
+    ) -> bool;
+}
+
+/// Pool that a commitment backend allocates funds to.
+///
+/// A pool holds what the backend sent it for a commitment and must hand it back on
+/// request, so settlements and exits can pay out in full. The backend checks that the
+/// funds arrived rather than trusting the call.
+#[contractclient(name = "AllocationPoolClient")]
+pub trait AllocationPoolInterface {
+    /// Transfer `amount` of `asset` allocated for `commitment_id` back to `to`.
+    fn withdraw(env: Env, commitment_id: String, asset: Address, amount: i128, to: Address);
 }
@@ -1,10 +1,183 @@
-use soroban_sdk::{contracttype, Address, BytesN};
+//! Commitment types shared by commitment backends and the contracts that read them.
+//! These are the canonical definitions; commitment_core re-exports them.
 
-#[derive(Clone, Debug, PartialEq)]
+use soroban_sdk::{contracttype, Address, Env, String, Vec};
+
+/// Risk profile selected at creation.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CommitmentType {
+    Safe,
+    Balanced,
+    Aggressive,
+}
+
+impl CommitmentType {
+    /// Lowercase name, as passed to the NFT contract and stored by earlier versions.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CommitmentType::Safe => "safe",
+            CommitmentType::Balanced => "balanced",
+            CommitmentType::Aggressive => "aggressive",
+        }
+    }
+
+    /// Parse a lowercase name as returned by `as_str`.
+    pub fn from_name(e: &Env, name: &String) -> Option<Self> {
+        [
+            CommitmentType::Safe,
+            CommitmentType::Balanced,
+            CommitmentType::Aggressive,
+        ]
+        .into_iter()
+        .find(|t| *name == String::from_str(e, t.as_str()))
+    }
+}
+
+/// Lifecycle state of a commitment.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CommitmentStatus {
+    Active,
+    /// Loss limit breached; becomes `Violated` unless it recovers within the grace window.
+    AtRisk,
+    Settled,
+    Violated,
+    EarlyExit,
+}
+
+impl CommitmentStatus {
+    /// Lowercase name, as stored by earlier versions.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CommitmentStatus::Active => "active",
+            CommitmentStatus::AtRisk => "at_risk",
+            CommitmentStatus::Settled => "settled",
+            CommitmentStatus::Violated => "violated",
+            CommitmentStatus::EarlyExit => "early_exit",
+        }
+    }
+
+    /// Whether the position is still live (`Active` or `AtRisk`).
+    pub fn is_open(&self) -> bool {
+        matches!(self, CommitmentStatus::Active | CommitmentStatus::AtRisk)
+    }
+
+    /// Parse a lowercase name as returned by `as_str`.
+    pub fn from_name(e: &Env, name: &String) -> Option<Self> {
+        [
+            CommitmentStatus::Active,
+            CommitmentStatus::AtRisk,
+            CommitmentStatus::Settled,
+            CommitmentStatus::Violated,
+            CommitmentStatus::EarlyExit,
+        ]
+        .into_iter()
+        .find(|s| *name == String::from_str(e, s.as_str()))
+    }
+}
+
+/// One row of a `PenaltySchedule::Steps` table.
 #[contracttype]
-pub struct CommitmentSpec {
-    pub provider: Address,
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PenaltyStep {
+    /// Applies while less than this percent of the term has elapsed.
+    pub until_elapsed_percent: u32,
+    pub penalty_percent: u32,
+}
+
+/// How the early-exit penalty changes over the commitment term.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PenaltySchedule {
+    /// `early_exit_penalty` for the whole term.
+    Flat,
+    /// Decays linearly from `early_exit_penalty` at creation to zero at expiry.
+    Linear,
+    /// Penalty from the first step whose `until_elapsed_percent` has not been reached,
+    /// zero after the last one. Steps must be in ascending order.
+    Steps(Vec<PenaltyStep>),
+}
+
+/// How a commitment's value becomes claimable by its owner.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ReleaseSchedule {
+    /// Everything at settlement after `expires_at`.
+    AtExpiry,
+    /// Vests linearly from creation to expiry; nothing is claimable until the cliff
+    /// (in days) has passed.
+    Linear(u32),
+    /// Vests in equal tranches, one per interval (in days) from creation; the last
+    /// tranche vests at expiry.
+    Tranches(u32),
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CommitmentRules {
+    pub duration_days: u32,
+    pub max_loss_percent: u32,
+    pub commitment_type: CommitmentType,
+    pub early_exit_penalty: u32,
+    pub min_fee_threshold: i128,
+    pub grace_period_days: u32,
+    pub penalty_schedule: PenaltySchedule,
+    pub penalty_floor_percent: u32, // minimum penalty whatever the schedule
+    pub auto_rollover: bool,        // restart for another term instead of paying out at settle
+    pub release_schedule: ReleaseSchedule,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Commitment {
+    pub commitment_id: String,
+    pub owner: Address,
+    pub nft_token_id: u32,
+    pub rules: CommitmentRules,
     pub amount: i128,
-    pub unlock_date: u64,
-    pub metadata_hash: BytesN<32>,
+    pub asset_address: Address,
+    pub created_at: u64,
+    pub expires_at: u64,
+    pub current_value: i128,
+    pub status: CommitmentStatus,
+}
+
+/// Action an operator may take on an owner's behalf.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OperatorScope {
+    EarlyExit,
+    Renew,
+    /// Allocating to pools through core, and allocating and rebalancing through
+    /// allocation_logic.
+    Allocate,
+}
+
+/// Canonical id of the `n`th commitment (`"c_0"`, `"c_1"`, ...).
+///
+/// Every contract that refers to a commitment uses this string form.
+pub fn commitment_id(e: &Env, n: u64) -> String {
+    let mut buf = [0u8; 22];
+    buf[0] = b'c';
+    buf[1] = b'_';
+    let mut i = 2;
+    if n == 0 {
+        buf[i] = b'0';
+        i += 1;
+    } else {
+        let mut digits = [0u8; 20];
+        let mut digit_count = 0;
+        let mut n = n;
+        while n > 0 {
+            digits[digit_count] = (n % 10) as u8 + b'0';
+            n /= 10;
+            digit_count += 1;
+        }
+        for j in 0..digit_count {
+            buf[i] = digits[digit_count - 1 - j];
+            i += 1;
+        }
+    }
+    String::from_bytes(e, &buf[..i])
 }
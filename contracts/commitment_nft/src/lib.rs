@@ -2,7 +2,7 @@
 use shared_utils::{EmergencyControl, Pausable};
 use soroban_sdk::{
     contract, contracterror, contractimpl, contracttype, symbol_short, Address, BytesN, Env,
-    String, Symbol, Vec,
+    IntoVal, String, Symbol, Vec,
 };
 
 const CURRENT_VERSION: u32 = 1;
@@ -85,6 +85,16 @@ pub struct CommitmentNFT {
     pub early_exit_penalty: u32,
 }
 
+/// One asset of a basket commitment. `metadata.asset_address` and `initial_amount` of a
+/// basket NFT hold the quote asset and the basket's aggregate value in it.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BasketLeg {
+    pub asset_address: Address,
+    pub amount: i128,
+    pub weight_bps: u32,
+}
+
 /// Parameters for batch NFT transfer operations
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -119,6 +129,8 @@ pub enum DataKey {
     ReentrancyGuard,
     /// Contract version
     Version,
+    /// Basket legs (token_id -> Vec<BasketLeg>) for basket commitments
+    Basket(u32),
 }
 
 // Events
@@ -493,14 +505,16 @@ impl CommitmentNFTContract {
     /// - **Ownership Validation**: Returns `NotOwner` error (#5) if `from` is not the current owner.
     ///   This ensures only the current owner can initiate transfers.
     ///
-    /// - **Lock Status Check**: Returns `NFTLocked` error (#19) if the NFT has an active commitment.
-    ///   Only settled/inactive NFTs can be transferred to prevent commitment state conflicts.
+    /// - **Lock Status Check**: Returns `NFTLocked` error (#19) if the NFT has an active commitment
+    ///   and no commitment_core contract is configured. With a core contract, active NFTs can be
+    ///   transferred: core is notified and the commitment (its payouts, early exit rights and
+    ///   owner index) follows the token.
     ///
     /// - **Token Existence**: Returns `TokenNotFound` error (#3) if the token does not exist.
     ///
     /// # Reentrancy Protection
-    /// Uses checks-effects-interactions pattern. This function only writes to storage
-    /// and doesn't make external calls, but still protected for consistency.
+    /// Uses checks-effects-interactions pattern with reentrancy guard. The only external
+    /// call, notifying commitment_core of the new owner, is made after all state updates.
     ///
     /// # Authorization
     /// Requires authorization from the `from` address via `from.require_auth()`.
@@ -558,8 +572,10 @@ impl CommitmentNFTContract {
             return Err(ContractError::NotOwner);
         }
 
-        // Check if NFT is active (locked) - active commitments cannot be transferred
-        if nft.is_active {
+        // Check if NFT is active (locked) - active commitments can only move together with
+        // the commitment, which requires a core contract to notify
+        let core_contract: Option<Address> = e.storage().instance().get(&DataKey::CoreContract);
+        if nft.is_active && core_contract.is_none() {
             e.storage()
                 .instance()
                 .set(&DataKey::ReentrancyGuard, &false);
@@ -619,6 +635,16 @@ impl CommitmentNFTContract {
             .persistent()
             .set(&DataKey::OwnerTokens(to.clone()), &to_tokens);
 
+        // INTERACTIONS: Move the underlying commitment to the new owner
+        if let Some(core_contract) = core_contract {
+            let mut args = Vec::new(&e);
+            args.push_back(nft.metadata.commitment_id.into_val(&e));
+            args.push_back(token_id.into_val(&e));
+            args.push_back(from.clone().into_val(&e));
+            args.push_back(to.clone().into_val(&e));
+            e.invoke_contract::<()>(&core_contract, &Symbol::new(&e, "on_nft_transfer"), args);
+        }
+
         // Clear reentrancy guard
         e.storage()
             .instance()
@@ -804,6 +830,184 @@ impl CommitmentNFTContract {
         Ok(())
     }
 
+    /// Update the recorded `initial_amount` of an active NFT.
+    /// Used by commitment_core when part of a position is withdrawn, so the NFT keeps
+    /// describing the principal that is still locked.
+    /// Only the configured commitment_core contract or admin may call this.
+    pub fn update_initial_amount(
+        e: Env,
+        caller: Address,
+        token_id: u32,
+        new_amount: i128,
+    ) -> Result<(), ContractError> {
+        EmergencyControl::require_not_emergency(&e);
+        Pausable::require_not_paused(&e);
+        require_core_or_admin(&e, &caller)?;
+
+        if new_amount <= 0 {
+            return Err(ContractError::InvalidAmount);
+        }
+
+        let mut nft: CommitmentNFT = e
+            .storage()
+            .persistent()
+            .get(&DataKey::NFT(token_id))
+            .ok_or(ContractError::TokenNotFound)?;
+        if !nft.is_active {
+            return Err(ContractError::AlreadySettled);
+        }
+
+        let old_amount = nft.metadata.initial_amount;
+        nft.metadata.initial_amount = new_amount;
+        e.storage().persistent().set(&DataKey::NFT(token_id), &nft);
+
+        e.events().publish(
+            (symbol_short!("AmtUpd"), token_id),
+            (old_amount, new_amount, e.ledger().timestamp()),
+        );
+
+        Ok(())
+    }
+
+    /// Re-stamp an active NFT for a renewed commitment term.
+    /// Used by commitment_core when a commitment rolls over at expiry, so the position
+    /// keeps its token (and its history) instead of being settled and minted again.
+    /// `created_at` is kept; the term restarts and `expires_at` is counted from now.
+    /// Only the configured commitment_core contract or admin may call this.
+    pub fn renew(
+        e: Env,
+        caller: Address,
+        token_id: u32,
+        duration_days: u32,
+        max_loss_percent: u32,
+        initial_amount: i128,
+        early_exit_penalty: u32,
+    ) -> Result<(), ContractError> {
+        EmergencyControl::require_not_emergency(&e);
+        Pausable::require_not_paused(&e);
+        require_core_or_admin(&e, &caller)?;
+
+        if duration_days == 0 {
+            return Err(ContractError::InvalidDuration);
+        }
+        if max_loss_percent > 100 {
+            return Err(ContractError::InvalidMaxLoss);
+        }
+        if initial_amount <= 0 {
+            return Err(ContractError::InvalidAmount);
+        }
+
+        let mut nft: CommitmentNFT = e
+            .storage()
+            .persistent()
+            .get(&DataKey::NFT(token_id))
+            .ok_or(ContractError::TokenNotFound)?;
+        if !nft.is_active {
+            return Err(ContractError::AlreadySettled);
+        }
+
+        let expires_at = e.ledger().timestamp() + (duration_days as u64 * 86400);
+        nft.metadata.duration_days = duration_days;
+        nft.metadata.max_loss_percent = max_loss_percent;
+        nft.metadata.initial_amount = initial_amount;
+        nft.metadata.expires_at = expires_at;
+        nft.early_exit_penalty = early_exit_penalty;
+        e.storage().persistent().set(&DataKey::NFT(token_id), &nft);
+
+        e.events().publish(
+            (symbol_short!("Renew"), token_id),
+            (expires_at, initial_amount, e.ledger().timestamp()),
+        );
+
+        Ok(())
+    }
+
+    /// Set the expiry of an active NFT to `expires_at`, which must be in the future.
+    /// Used by commitment_core for commitments split off another, which keep the
+    /// original's exact expiry rather than a whole number of days from now.
+    /// Only the configured commitment_core contract or admin may call this.
+    pub fn set_expires_at(
+        e: Env,
+        caller: Address,
+        token_id: u32,
+        expires_at: u64,
+    ) -> Result<(), ContractError> {
+        EmergencyControl::require_not_emergency(&e);
+        Pausable::require_not_paused(&e);
+        require_core_or_admin(&e, &caller)?;
+
+        if expires_at <= e.ledger().timestamp() {
+            return Err(ContractError::InvalidDuration);
+        }
+
+        let mut nft: CommitmentNFT = e
+            .storage()
+            .persistent()
+            .get(&DataKey::NFT(token_id))
+            .ok_or(ContractError::TokenNotFound)?;
+        if !nft.is_active {
+            return Err(ContractError::AlreadySettled);
+        }
+
+        nft.metadata.expires_at = expires_at;
+        e.storage().persistent().set(&DataKey::NFT(token_id), &nft);
+
+        e.events().publish(
+            (symbol_short!("Expiry"), token_id),
+            (expires_at, e.ledger().timestamp()),
+        );
+
+        Ok(())
+    }
+
+    /// Record the legs of a basket commitment on its active NFT.
+    /// Only the configured commitment_core contract or admin may call this.
+    pub fn set_basket(
+        e: Env,
+        caller: Address,
+        token_id: u32,
+        legs: Vec<BasketLeg>,
+    ) -> Result<(), ContractError> {
+        EmergencyControl::require_not_emergency(&e);
+        Pausable::require_not_paused(&e);
+        require_core_or_admin(&e, &caller)?;
+
+        if legs.is_empty() || legs.iter().any(|leg| leg.amount <= 0) {
+            return Err(ContractError::InvalidAmount);
+        }
+
+        let nft: CommitmentNFT = e
+            .storage()
+            .persistent()
+            .get(&DataKey::NFT(token_id))
+            .ok_or(ContractError::TokenNotFound)?;
+        if !nft.is_active {
+            return Err(ContractError::AlreadySettled);
+        }
+
+        e.storage()
+            .persistent()
+            .set(&DataKey::Basket(token_id), &legs);
+
+        e.events().publish(
+            (symbol_short!("Basket"), token_id),
+            (legs.len(), e.ledger().timestamp()),
+        );
+
+        Ok(())
+    }
+
+    /// Get the basket legs of an NFT; empty for single-asset commitments.
+    pub fn get_basket(e: Env, token_id: u32) -> Result<Vec<BasketLeg>, ContractError> {
+        if !e.storage().persistent().has(&DataKey::NFT(token_id)) {
+            return Err(ContractError::TokenNotFound);
+        }
+        Ok(e.storage()
+            .persistent()
+            .get(&DataKey::Basket(token_id))
+            .unwrap_or(Vec::new(&e)))
+    }
+
     /// Check if an NFT has expired (based on time)
     pub fn is_expired(e: Env, token_id: u32) -> Result<bool, ContractError> {
         let nft: CommitmentNFT = e
@@ -859,6 +1063,25 @@ fn require_admin(e: &Env, caller: &Address) -> Result<(), ContractError> {
     Ok(())
 }
 
+/// Allow only the configured commitment_core contract or the admin.
+fn require_core_or_admin(e: &Env, caller: &Address) -> Result<(), ContractError> {
+    let core_contract: Address = e
+        .storage()
+        .instance()
+        .get(&DataKey::CoreContract)
+        .ok_or(ContractError::NotInitialized)?;
+    let admin: Address = e
+        .storage()
+        .instance()
+        .get(&DataKey::Admin)
+        .ok_or(ContractError::NotInitialized)?;
+    if *caller != core_contract && *caller != admin {
+        return Err(ContractError::NotAuthorized);
+    }
+    caller.require_auth();
+    Ok(())
+}
+
 fn require_valid_wasm_hash(e: &Env, wasm_hash: &BytesN<32>) -> Result<(), ContractError> {
     let zero = BytesN::from_array(e, &[0; 32]);
     if *wasm_hash == zero {
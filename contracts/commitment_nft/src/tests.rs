@@ -4,7 +4,7 @@ extern crate std;
 
 use crate::*;
 use soroban_sdk::{
-    symbol_short,
+    contract, contractimpl, symbol_short,
     testutils::{Address as _, Events, Ledger},
     vec, Address, Env, IntoVal, String,
 };
@@ -16,13 +16,36 @@ fn setup_contract(e: &Env) -> (Address, CommitmentNFTContractClient<'_>) {
     (admin, client)
 }
 
+/// Stand-in for commitment_core that records the ownership notifications sent by `transfer`.
+#[contract]
+pub struct MockCoreContract;
+
+#[contractimpl]
+impl MockCoreContract {
+    pub fn on_nft_transfer(
+        e: Env,
+        commitment_id: String,
+        token_id: u32,
+        from: Address,
+        to: Address,
+    ) {
+        e.storage()
+            .instance()
+            .set(&symbol_short!("last"), &(commitment_id, token_id, from, to));
+    }
+
+    pub fn last_transfer(e: Env) -> Option<(String, u32, Address, Address)> {
+        e.storage().instance().get(&symbol_short!("last"))
+    }
+}
+
 /// Setup contract with a registered "core" contract so settle() can be called (access control).
 /// Returns (admin, client, core_contract_id). Use e.as_contract(&core_id, || { client.settle(&core_id, &token_id) }) to settle.
 fn setup_contract_with_core(e: &Env) -> (Address, CommitmentNFTContractClient<'_>, Address) {
     e.mock_all_auths();
     let (admin, client) = setup_contract(e);
     client.initialize(&admin);
-    let core_id = e.register_contract(None, CommitmentNFTContract);
+    let core_id = e.register_contract(None, MockCoreContract);
     let _ = client.set_core_contract(&core_id);
     (admin, client, core_id)
 }
@@ -1346,6 +1369,248 @@ fn test_settle_by_core_contract_succeeds() {
     assert_eq!(client.is_active(&token_id), false);
 }
 
+// ============================================
+// update_initial_amount Tests
+// ============================================
+
+#[test]
+fn test_update_initial_amount_by_core_contract() {
+    let e = Env::default();
+    let (_admin, client, core_id) = setup_contract_with_core(&e);
+    let owner = Address::generate(&e);
+    let asset_address = Address::generate(&e);
+
+    let token_id = mint_to_owner(&e, &client, &owner, &asset_address, "partial_001");
+    client.update_initial_amount(&core_id, &token_id, &600);
+
+    let nft = client.get_metadata(&token_id);
+    assert_eq!(nft.metadata.initial_amount, 600);
+    assert!(nft.is_active);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #6)")] // NotAuthorized
+fn test_update_initial_amount_by_random_address_fails() {
+    let e = Env::default();
+    let (_admin, client, _core_id) = setup_contract_with_core(&e);
+    let owner = Address::generate(&e);
+    let asset_address = Address::generate(&e);
+
+    let token_id = mint_to_owner(&e, &client, &owner, &asset_address, "partial_002");
+    client.update_initial_amount(&owner, &token_id, &600);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #8)")] // AlreadySettled
+fn test_update_initial_amount_settled_nft_fails() {
+    let e = Env::default();
+    let (_admin, client, core_id) = setup_contract_with_core(&e);
+    let owner = Address::generate(&e);
+    let asset_address = Address::generate(&e);
+
+    let token_id = mint_to_owner(&e, &client, &owner, &asset_address, "partial_003");
+    e.ledger().with_mut(|li| {
+        li.timestamp = 172800;
+    });
+    client.settle(&core_id, &token_id);
+    client.update_initial_amount(&core_id, &token_id, &600);
+}
+
+// ============================================
+// Commitment ownership Tests
+// ============================================
+
+#[test]
+fn test_transfer_active_nft_notifies_core() {
+    let e = Env::default();
+    let (_admin, client, core_id) = setup_contract_with_core(&e);
+    let core = MockCoreContractClient::new(&e, &core_id);
+    let owner = Address::generate(&e);
+    let recipient = Address::generate(&e);
+    let asset_address = Address::generate(&e);
+
+    let token_id = mint_to_owner(&e, &client, &owner, &asset_address, "owner_001");
+    assert!(client.is_active(&token_id));
+    client.transfer(&owner, &recipient, &token_id);
+
+    assert_eq!(client.owner_of(&token_id), recipient);
+    assert!(client.is_active(&token_id));
+    assert_eq!(
+        core.last_transfer(),
+        Some((
+            String::from_str(&e, "owner_001"),
+            token_id,
+            owner.clone(),
+            recipient.clone()
+        ))
+    );
+    assert_balance_supply_invariant(&client, &[&owner, &recipient]);
+}
+
+#[test]
+fn test_transfer_settled_nft_notifies_core() {
+    let e = Env::default();
+    let (_admin, client, core_id) = setup_contract_with_core(&e);
+    let core = MockCoreContractClient::new(&e, &core_id);
+    let owner = Address::generate(&e);
+    let recipient = Address::generate(&e);
+    let asset_address = Address::generate(&e);
+
+    let token_id = mint_to_owner(&e, &client, &owner, &asset_address, "owner_002");
+    e.ledger().with_mut(|li| {
+        li.timestamp = 172800;
+    });
+    client.settle(&core_id, &token_id);
+    client.transfer(&owner, &recipient, &token_id);
+
+    let (_, notified_token, from, to) = core.last_transfer().unwrap();
+    assert_eq!(notified_token, token_id);
+    assert_eq!(from, owner);
+    assert_eq!(to, recipient);
+}
+
+// ============================================
+// renew Tests
+// ============================================
+
+#[test]
+fn test_renew_restamps_active_nft() {
+    let e = Env::default();
+    let (_admin, client, core_id) = setup_contract_with_core(&e);
+    let owner = Address::generate(&e);
+    let asset_address = Address::generate(&e);
+
+    let token_id = mint_to_owner(&e, &client, &owner, &asset_address, "renew_001");
+    e.ledger().with_mut(|li| {
+        li.timestamp = 172800;
+    });
+    assert!(client.is_expired(&token_id));
+
+    client.renew(&core_id, &token_id, &7, &20, &1200, &3);
+
+    let nft = client.get_metadata(&token_id);
+    assert_eq!(nft.owner, owner);
+    assert!(nft.is_active);
+    assert_eq!(nft.metadata.created_at, 0);
+    assert_eq!(nft.metadata.expires_at, 172800 + 7 * 86400);
+    assert_eq!(nft.metadata.duration_days, 7);
+    assert_eq!(nft.metadata.max_loss_percent, 20);
+    assert_eq!(nft.metadata.initial_amount, 1200);
+    assert_eq!(nft.early_exit_penalty, 3);
+    assert!(!client.is_expired(&token_id));
+    assert_eq!(client.total_supply(), 1);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #6)")] // NotAuthorized
+fn test_renew_by_random_address_fails() {
+    let e = Env::default();
+    let (_admin, client, _core_id) = setup_contract_with_core(&e);
+    let owner = Address::generate(&e);
+    let asset_address = Address::generate(&e);
+
+    let token_id = mint_to_owner(&e, &client, &owner, &asset_address, "renew_002");
+    client.renew(&owner, &token_id, &7, &20, &1200, &3);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #8)")] // AlreadySettled
+fn test_renew_settled_nft_fails() {
+    let e = Env::default();
+    let (_admin, client, core_id) = setup_contract_with_core(&e);
+    let owner = Address::generate(&e);
+    let asset_address = Address::generate(&e);
+
+    let token_id = mint_to_owner(&e, &client, &owner, &asset_address, "renew_003");
+    e.ledger().with_mut(|li| {
+        li.timestamp = 172800;
+    });
+    client.settle(&core_id, &token_id);
+    client.renew(&core_id, &token_id, &7, &20, &1200, &3);
+}
+
+// ============================================
+// set_expires_at Tests
+// ============================================
+
+#[test]
+fn test_set_expires_at_by_core() {
+    let e = Env::default();
+    let (_admin, client, core_id) = setup_contract_with_core(&e);
+    let owner = Address::generate(&e);
+    let asset_address = Address::generate(&e);
+
+    let token_id = mint_to_owner(&e, &client, &owner, &asset_address, "expiry_001");
+    client.set_expires_at(&core_id, &token_id, &(86400 + 3600));
+
+    let nft = client.get_metadata(&token_id);
+    assert_eq!(nft.metadata.expires_at, 86400 + 3600);
+    e.ledger().with_mut(|li| {
+        li.timestamp = 86400;
+    });
+    assert!(!client.is_expired(&token_id));
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #6)")] // NotAuthorized
+fn test_set_expires_at_by_random_address_fails() {
+    let e = Env::default();
+    let (_admin, client, _core_id) = setup_contract_with_core(&e);
+    let owner = Address::generate(&e);
+    let asset_address = Address::generate(&e);
+
+    let token_id = mint_to_owner(&e, &client, &owner, &asset_address, "expiry_002");
+    client.set_expires_at(&owner, &token_id, &(86400 + 3600));
+}
+
+// ============================================
+// set_basket Tests
+// ============================================
+
+#[test]
+fn test_set_basket_records_legs() {
+    let e = Env::default();
+    let (_admin, client, core_id) = setup_contract_with_core(&e);
+    let owner = Address::generate(&e);
+    let quote_asset = Address::generate(&e);
+
+    let token_id = mint_to_owner(&e, &client, &owner, &quote_asset, "basket_001");
+    assert_eq!(client.get_basket(&token_id).len(), 0);
+
+    let mut legs = Vec::new(&e);
+    legs.push_back(BasketLeg {
+        asset_address: Address::generate(&e),
+        amount: 600,
+        weight_bps: 6000,
+    });
+    legs.push_back(BasketLeg {
+        asset_address: Address::generate(&e),
+        amount: 400,
+        weight_bps: 4000,
+    });
+    client.set_basket(&core_id, &token_id, &legs);
+
+    assert_eq!(client.get_basket(&token_id), legs);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #6)")] // NotAuthorized
+fn test_set_basket_by_random_address_fails() {
+    let e = Env::default();
+    let (_admin, client, _core_id) = setup_contract_with_core(&e);
+    let owner = Address::generate(&e);
+    let quote_asset = Address::generate(&e);
+
+    let token_id = mint_to_owner(&e, &client, &owner, &quote_asset, "basket_002");
+    let mut legs = Vec::new(&e);
+    legs.push_back(BasketLeg {
+        asset_address: Address::generate(&e),
+        amount: 600,
+        weight_bps: 10000,
+    });
+    client.set_basket(&owner, &token_id, &legs);
+}
+
 // ============================================
 // is_expired Tests
 // ============================================
@@ -298,7 +298,7 @@ fn test_allocation_logic_pool_interaction() {
             AllocationStrategiesContract::allocate(
                 harness.env.clone(),
                 user.clone(),
-                1u64, // commitment_id
+                String::from_str(&harness.env, "c_0"), // commitment_id
                 amount,
                 Strategy::Balanced,
             )
@@ -341,7 +341,7 @@ fn test_allocation_rebalance_cross_pool() {
             AllocationStrategiesContract::allocate(
                 harness.env.clone(),
                 user.clone(),
-                1u64,
+                String::from_str(&harness.env, "c_0"),
                 amount,
                 Strategy::Balanced,
             )
@@ -352,7 +352,7 @@ fn test_allocation_rebalance_cross_pool() {
     let initial_allocation = harness
         .env
         .as_contract(&harness.contracts.allocation_logic, || {
-            AllocationStrategiesContract::get_allocation(harness.env.clone(), 1u64)
+            AllocationStrategiesContract::get_allocation(harness.env.clone(), String::from_str(&harness.env, "c_0"))
         });
 
     // Advance time
@@ -362,7 +362,7 @@ fn test_allocation_rebalance_cross_pool() {
     let result = harness
         .env
         .as_contract(&harness.contracts.allocation_logic, || {
-            AllocationStrategiesContract::rebalance(harness.env.clone(), user.clone(), 1u64)
+            AllocationStrategiesContract::rebalance(harness.env.clone(), user.clone(), String::from_str(&harness.env, "c_0"))
         });
 
     assert!(result.is_ok());
@@ -349,7 +349,7 @@ fn test_e2e_commitment_with_allocation() {
             AllocationStrategiesContract::allocate(
                 harness.env.clone(),
                 user.clone(),
-                1u64,
+                commitment_id.clone(),
                 amount,
                 Strategy::Balanced,
             )
@@ -363,7 +363,7 @@ fn test_e2e_commitment_with_allocation() {
     let allocation = harness
         .env
         .as_contract(&harness.contracts.allocation_logic, || {
-            AllocationStrategiesContract::get_allocation(harness.env.clone(), 1u64)
+            AllocationStrategiesContract::get_allocation(harness.env.clone(), commitment_id.clone())
         });
     assert_eq!(allocation.strategy, Strategy::Balanced);
     assert!(allocation.allocations.len() > 0);
@@ -630,7 +630,7 @@ fn test_e2e_allocation_rebalancing_flow() {
             AllocationStrategiesContract::allocate(
                 harness.env.clone(),
                 user.clone(),
-                1u64,
+                String::from_str(&harness.env, "c_0"),
                 amount,
                 Strategy::Balanced,
             )
@@ -647,7 +647,7 @@ fn test_e2e_allocation_rebalancing_flow() {
     let rebalance_result = harness
         .env
         .as_contract(&harness.contracts.allocation_logic, || {
-            AllocationStrategiesContract::rebalance(harness.env.clone(), user.clone(), 1u64)
+            AllocationStrategiesContract::rebalance(harness.env.clone(), user.clone(), String::from_str(&harness.env, "c_0"))
         });
     assert!(rebalance_result.is_ok());
 
@@ -658,7 +658,7 @@ fn test_e2e_allocation_rebalancing_flow() {
     let final_allocation = harness
         .env
         .as_contract(&harness.contracts.allocation_logic, || {
-            AllocationStrategiesContract::get_allocation(harness.env.clone(), 1u64)
+            AllocationStrategiesContract::get_allocation(harness.env.clone(), String::from_str(&harness.env, "c_0"))
         });
     assert_eq!(final_allocation.total_allocated, amount);
 }
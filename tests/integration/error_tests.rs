@@ -372,7 +372,7 @@ fn test_error_zero_amount_allocation() {
             AllocationStrategiesContract::allocate(
                 harness.env.clone(),
                 user.clone(),
-                1u64,
+                String::from_str(&harness.env, "c_0"),
                 0, // Zero amount
                 Strategy::Balanced,
             )
@@ -395,7 +395,7 @@ fn test_error_negative_amount_allocation() {
             AllocationStrategiesContract::allocate(
                 harness.env.clone(),
                 user.clone(),
-                1u64,
+                String::from_str(&harness.env, "c_0"),
                 -1000, // Negative amount
                 Strategy::Balanced,
             )
@@ -424,7 +424,7 @@ fn test_error_double_allocation() {
             AllocationStrategiesContract::allocate(
                 harness.env.clone(),
                 user.clone(),
-                1u64,
+                String::from_str(&harness.env, "c_0"),
                 amount,
                 Strategy::Balanced,
             )
@@ -438,7 +438,7 @@ fn test_error_double_allocation() {
             AllocationStrategiesContract::allocate(
                 harness.env.clone(),
                 user.clone(),
-                1u64, // Same commitment_id
+                String::from_str(&harness.env, "c_0"), // Same commitment_id
                 amount,
                 Strategy::Balanced,
             )
@@ -775,7 +775,7 @@ fn test_error_allocation_no_pools() {
             AllocationStrategiesContract::allocate(
                 harness.env.clone(),
                 user.clone(),
-                1u64,
+                String::from_str(&harness.env, "c_0"),
                 1_000_000_000_000,
                 Strategy::Balanced,
             )
@@ -798,7 +798,7 @@ fn test_error_rebalance_nonexistent() {
             AllocationStrategiesContract::rebalance(
                 harness.env.clone(),
                 user.clone(),
-                99999u64, // Non-existent
+                String::from_str(&harness.env, "c_99999"), // Non-existent
             )
         });
 
@@ -4,7 +4,10 @@
 #![cfg(test)]
 
 use attestation_engine::{AttestationEngineContract, AttestationEngineContractClient};
-use commitment_core::{CommitmentCoreContract, CommitmentCoreContractClient, CommitmentRules};
+use commitment_core::{
+    CommitmentCoreContract, CommitmentCoreContractClient, CommitmentRules, CommitmentStatus,
+    CommitmentType, PenaltySchedule, ReleaseSchedule,
+};
 use commitment_nft::{CommitmentNFTContract, CommitmentNFTContractClient};
 use price_oracle::{PriceOracleContract, PriceOracleContractClient};
 use soroban_sdk::{
@@ -68,10 +71,14 @@ impl IntegrationTestFixture {
         CommitmentRules {
             duration_days: 30,
             max_loss_percent: 10,
-            commitment_type: String::from_str(&self.env, "safe"),
+            commitment_type: CommitmentType::Safe,
             early_exit_penalty: 5,
             min_fee_threshold: 100_0000000,
             grace_period_days: 3,
+            penalty_schedule: PenaltySchedule::Flat,
+            penalty_floor_percent: 0,
+            auto_rollover: false,
+            release_schedule: ReleaseSchedule::AtExpiry,
         }
     }
 }
@@ -98,7 +105,7 @@ fn test_create_commitment_with_attestation_flow() {
     let commitment = fixture.core_client.get_commitment(&commitment_id);
     assert_eq!(commitment.owner, fixture.owner);
     assert_eq!(commitment.amount, 1000_0000000);
-    assert_eq!(commitment.status, String::from_str(&fixture.env, "active"));
+    assert_eq!(commitment.status, CommitmentStatus::Active);
 
     // Step 2: Record attestation for the commitment
     let mut data = Map::new(&fixture.env);
@@ -128,10 +135,14 @@ fn test_commitment_value_update_with_health_tracking() {
     let rules = CommitmentRules {
         duration_days: 30,
         max_loss_percent: 10,
-        commitment_type: String::from_str(&fixture.env, "balanced"),
+        commitment_type: CommitmentType::Balanced,
         early_exit_penalty: 5,
         min_fee_threshold: 100_0000000,
         grace_period_days: 3,
+        penalty_schedule: PenaltySchedule::Flat,
+        penalty_floor_percent: 0,
+        auto_rollover: false,
+        release_schedule: ReleaseSchedule::AtExpiry,
     };
 
     // Create commitment
@@ -164,7 +175,7 @@ fn test_commitment_value_update_with_health_tracking() {
     // Verify commitment status
     let commitment = fixture.core_client.get_commitment(&commitment_id);
     assert_eq!(commitment.current_value, 1050_0000000);
-    assert_eq!(commitment.status, String::from_str(&fixture.env, "active"));
+    assert_eq!(commitment.status, CommitmentStatus::Active);
 }
 
 #[test]
@@ -198,10 +209,7 @@ fn test_settlement_flow_end_to_end() {
 
     // Verify commitment is settled
     let settled_commitment = fixture.core_client.get_commitment(&commitment_id);
-    assert_eq!(
-        settled_commitment.status,
-        String::from_str(&fixture.env, "settled")
-    );
+    assert_eq!(settled_commitment.status, CommitmentStatus::Settled);
 }
 
 #[test]
@@ -212,10 +220,14 @@ fn test_early_exit_flow_end_to_end() {
     let rules = CommitmentRules {
         duration_days: 30,
         max_loss_percent: 10,
-        commitment_type: String::from_str(&fixture.env, "aggressive"),
+        commitment_type: CommitmentType::Aggressive,
         early_exit_penalty: 10,
         min_fee_threshold: 100_0000000,
         grace_period_days: 3,
+        penalty_schedule: PenaltySchedule::Flat,
+        penalty_floor_percent: 0,
+        auto_rollover: false,
+        release_schedule: ReleaseSchedule::AtExpiry,
     };
 
     // Create commitment
@@ -253,10 +265,7 @@ fn test_early_exit_flow_end_to_end() {
 
     // Verify commitment is marked as early exit
     let commitment = fixture.core_client.get_commitment(&commitment_id);
-    assert_eq!(
-        commitment.status,
-        String::from_str(&fixture.env, "early_exit")
-    );
+    assert_eq!(commitment.status, CommitmentStatus::EarlyExit);
 }
 
 #[test]